@@ -1,100 +1,638 @@
-type WordData = (String, isize);
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Cursor},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Instant,
+};
+
+type WordData = (&'static str, isize);
 type DigitData = (char, isize);
 
+const WORDS: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+const TEENS: [&str; 10] = [
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 8] = [
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+const DIGITS: [char; 9] = ['1', '2', '3', '4', '5', '6', '7', '8', '9'];
+/// The zero of every run of ten decimal digits (general category Nd) as of
+/// Unicode 17, in ascending order. Digits added by later Unicode versions are
+/// not recognised until this table is updated; compare with
+/// `char::UNICODE_VERSION` when moving to a newer toolchain.
+const UNICODE_DIGIT_ZEROS: [u32; 77] = [
+    0x0030, 0x0660, 0x06F0, 0x07C0, 0x0966, 0x09E6, 0x0A66, 0x0AE6, 0x0B66, 0x0BE6, 0x0C66, 0x0CE6,
+    0x0D66, 0x0DE6, 0x0E50, 0x0ED0, 0x0F20, 0x1040, 0x1090, 0x17E0, 0x1810, 0x1946, 0x19D0, 0x1A80,
+    0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0, 0xA9F0, 0xAA50, 0xABF0,
+    0xFF10, 0x104A0, 0x10D30, 0x10D40, 0x11066, 0x110F0, 0x11136, 0x111D0, 0x112F0, 0x11450,
+    0x114D0, 0x11650, 0x116C0, 0x116D0, 0x116DA, 0x11730, 0x118E0, 0x11950, 0x11BF0, 0x11C50,
+    0x11D50, 0x11DA0, 0x11DE0, 0x11F50, 0x16130, 0x16A60, 0x16AC0, 0x16B50, 0x16D70, 0x1CCF0,
+    0x1D7CE, 0x1D7D8, 0x1D7E2, 0x1D7EC, 0x1D7F6, 0x1E140, 0x1E2F0, 0x1E4F0, 0x1E5F1, 0x1E950,
+    0x1FBF0,
+];
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Which tokens of a line take part in its calibration value.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenSelection {
+    /// The first `first` tokens followed by the last `last` tokens. Both ends are
+    /// picked independently, so a line with a single token yields it on each side.
+    Ends {
+        first: usize,
+        last: usize,
+    },
+    All,
+}
+
+/// How the selected tokens are folded into a single value.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Combination {
+    Concatenate,
+    Sum,
+    Product,
+}
+
+/// How a token worth more than one digit, such as "42" or "twenty", takes part
+/// in the combination.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MultiDigit {
+    /// The whole value, so concatenating 4 and "twenty" gives 420.
+    Whole,
+    /// Only its leading digit, so "twenty" counts as 2.
+    LeadingDigit,
+    /// Only its trailing digit, so "twenty" counts as 0.
+    TrailingDigit,
+}
+
+/// The tokens a line is scanned for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vocabulary {
+    /// Spelled-out numbers and the value each one stands for.
+    pub words: Vec<(String, u64)>,
+    /// Reads a run of digits such as "42" as a single token.
+    pub multi_digit_numerals: bool,
+    /// Accepts '0' as a digit on its own.
+    pub zero: bool,
+    /// Joins a tens word with a unit word right after it, so "twentyone" is 21.
+    pub compounds: bool,
+    /// Which characters count as digits, in numerals as well as on their own.
+    pub digit_mode: DigitMode,
+}
+
+/// Which characters count as digits.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DigitMode {
+    /// Only '0' to '9'.
+    Ascii,
+    /// Any Unicode decimal digit, such as the Arabic-Indic '٣' or the
+    /// full-width '３'.
+    Unicode,
+}
+
+impl DigitMode {
+    pub fn digit_value(self, ch: char) -> Option<u32> {
+        return match self {
+            DigitMode::Ascii => ch.to_digit(10),
+            DigitMode::Unicode => unicode_digit_value(ch),
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationRule {
+    pub selection: TokenSelection,
+    pub combination: Combination,
+    pub multi_digit: MultiDigit,
+    pub vocabulary: Vocabulary,
+}
+
+#[allow(dead_code)]
+impl CalibrationRule {
+    pub fn part_1() -> Self {
+        CalibrationRule {
+            selection: TokenSelection::Ends { first: 1, last: 1 },
+            combination: Combination::Concatenate,
+            multi_digit: MultiDigit::Whole,
+            vocabulary: Vocabulary::digits(),
+        }
+    }
+
+    pub fn part_2() -> Self {
+        CalibrationRule {
+            vocabulary: Vocabulary::single_digit_words(),
+            ..CalibrationRule::part_1()
+        }
+    }
+}
+
 #[allow(dead_code)]
 pub fn run() {
     run_part_2();
 }
 
+pub fn run_command(args: &[String]) {
+    match args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>()[..] {
+        ["parallel"] => run_part_2_parallel(),
+        ["unicode"] => run_part_2_unicode(),
+        ["extended"] => run_with_rule(&CalibrationRule {
+            vocabulary: Vocabulary::extended(),
+            ..CalibrationRule::part_2()
+        }),
+        ["bench"] => run_bench(10),
+        ["bench", repeat] => run_bench(repeat.parse().expect("repeat must be a number")),
+        _ => run(),
+    }
+}
+
 #[allow(dead_code)]
 fn run_part_1() {
-    let contents = std::fs::read_to_string("input/day1.txt").unwrap();
-
-    let mut sum = 0;
+    let file = File::open("input/day1.txt").unwrap();
 
-    for line in contents.lines() {
-        let combined = get_combined_of_line_with_only_digits(line.to_string());
-        let combined = combined.parse::<i32>().unwrap();
-        sum += combined;
-    }
+    let sum = sum_calibration_values(BufReader::new(file), get_combined_of_line_with_only_digits)
+        .unwrap();
 
     println!("sum is {}", sum);
 }
 
 #[allow(dead_code)]
 fn run_part_2() {
+    let file = File::open("input/day1.txt").unwrap();
+
+    let sum = sum_calibration_values(BufReader::new(file), get_combined_of_line_considering_words)
+        .unwrap();
+
+    println!("sum is {}", sum);
+}
+
+fn run_part_2_unicode() {
+    let file = File::open("input/day1.txt").unwrap();
+
+    let sum = sum_calibration_values(BufReader::new(file), |line| {
+        get_combined_of_line_with_digit_mode(line, DigitMode::Unicode)
+    })
+    .unwrap();
+
+    println!("sum is {}", sum);
+}
+
+fn run_part_2_parallel() {
     let contents = std::fs::read_to_string("input/day1.txt").unwrap();
 
+    let sum = sum_calibration_values_parallel(
+        &contents,
+        default_thread_count(),
+        CHUNK_SIZE,
+        get_combined_of_line_considering_words,
+    );
+
+    println!("sum is {}", sum);
+}
+
+/// Compares the sequential and parallel part 2 paths on the puzzle input
+/// repeated `repeat` times.
+fn run_bench(repeat: usize) {
+    let contents = std::fs::read_to_string("input/day1.txt")
+        .unwrap()
+        .repeat(repeat);
+    let threads = default_thread_count();
+
+    println!(
+        "benchmarking {} lines ({} bytes) on {} threads",
+        contents.lines().count(),
+        contents.len(),
+        threads
+    );
+
+    let start = Instant::now();
+    let sequential = sum_calibration_values(
+        Cursor::new(contents.as_bytes()),
+        get_combined_of_line_considering_words,
+    )
+    .unwrap();
+    println!("sequential: sum {} in {:?}", sequential, start.elapsed());
+
+    let start = Instant::now();
+    let parallel = sum_calibration_values_parallel(
+        &contents,
+        threads,
+        CHUNK_SIZE,
+        get_combined_of_line_considering_words,
+    );
+    println!("parallel:   sum {} in {:?}", parallel, start.elapsed());
+
+    assert_eq!(sequential, parallel);
+}
+
+fn default_thread_count() -> usize {
+    return thread::available_parallelism().map_or(1, |threads| threads.get());
+}
+
+fn run_with_rule(rule: &CalibrationRule) {
+    let file = File::open("input/day1.txt").unwrap();
+
+    let sum = sum_calibration_values(BufReader::new(file), |line| {
+        extract_calibration_value(line, rule)
+    })
+    .unwrap();
+
+    println!("sum is {}", sum);
+}
+
+/// Sums the calibration value of every line in `reader`, reusing a single line
+/// buffer so memory stays constant no matter how large the document is.
+fn sum_calibration_values<R: BufRead>(
+    mut reader: R,
+    get_combined: impl Fn(&str) -> u64,
+) -> io::Result<u64> {
+    let mut line = String::new();
     let mut sum = 0;
 
-    for line in contents.lines() {
-        let combined = get_combined_of_line_considering_words(line.to_string());
-        let combined = combined.parse::<i32>().unwrap();
-        sum += combined;
+    while reader.read_line(&mut line)? != 0 {
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+        sum += get_combined(trimmed);
+        line.clear();
     }
 
-    println!("sum is {}", sum);
+    return Ok(sum);
+}
+
+/// Sums the calibration value of every line in `contents` on a pool of `threads`
+/// scoped workers. The input is cut at line boundaries into chunks of roughly
+/// `chunk_size` bytes, which the workers pull from a shared counter until none
+/// are left.
+fn sum_calibration_values_parallel(
+    contents: &str,
+    threads: usize,
+    chunk_size: usize,
+    get_combined: impl Fn(&str) -> u64 + Sync,
+) -> u64 {
+    let chunks = split_at_line_boundaries(contents, chunk_size);
+    let next_chunk = AtomicUsize::new(0);
+
+    return thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut sum = 0;
+
+                    while let Some(chunk) = chunks.get(next_chunk.fetch_add(1, Ordering::Relaxed)) {
+                        sum += chunk.lines().map(&get_combined).sum::<u64>();
+                    }
+
+                    return sum;
+                })
+            })
+            .collect();
+
+        return workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .sum();
+    });
+}
+
+/// Cuts `contents` into pieces of at least `chunk_size` bytes (the last one may
+/// be shorter), each ending right after a newline so no line is split in two.
+fn split_at_line_boundaries(contents: &str, chunk_size: usize) -> Vec<&str> {
+    let mut chunks = vec![];
+    let mut rest = contents;
+
+    while !rest.is_empty() {
+        let minimum_end = chunk_size.clamp(1, rest.len());
+        let end = match rest.as_bytes()[minimum_end - 1..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+        {
+            Some(offset) => minimum_end + offset,
+            None => rest.len(),
+        };
+
+        let (chunk, remaining) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = remaining;
+    }
+
+    return chunks;
+}
+
+/// Extracts the calibration value of `line` according to `rule`. Lines without
+/// any token are worth zero, and values too large for a `u64` saturate at
+/// `u64::MAX`.
+pub fn extract_calibration_value(line: &str, rule: &CalibrationRule) -> u64 {
+    let tokens = Tokens::new(line, &rule.vocabulary);
+    let count = tokens.clone().count();
+
+    if count == 0 {
+        return 0;
+    }
+
+    return match rule.selection {
+        TokenSelection::All => combine_tokens(tokens, rule),
+        TokenSelection::Ends { first, last } => combine_tokens(
+            tokens
+                .clone()
+                .take(first)
+                .chain(tokens.skip(count.saturating_sub(last))),
+            rule,
+        ),
+    };
+}
+
+fn combine_tokens(tokens: impl Iterator<Item = u64>, rule: &CalibrationRule) -> u64 {
+    let values = tokens.map(|token| match rule.multi_digit {
+        MultiDigit::Whole => token,
+        MultiDigit::LeadingDigit => token / 10u64.pow(count_digits(token) - 1),
+        MultiDigit::TrailingDigit => token % 10,
+    });
+
+    return match rule.combination {
+        Combination::Concatenate => values.fold(0, |acc: u64, value| {
+            10u64
+                .checked_pow(count_digits(value))
+                .and_then(|shift| acc.checked_mul(shift))
+                .and_then(|shifted| shifted.checked_add(value))
+                .unwrap_or(u64::MAX)
+        }),
+        Combination::Sum => values.fold(0, u64::saturating_add),
+        Combination::Product => values.fold(1, u64::saturating_mul),
+    };
+}
+
+fn count_digits(value: u64) -> u32 {
+    return value.checked_ilog10().unwrap_or(0) + 1;
+}
+
+/// Walks `line` from left to right yielding the value of every token in the
+/// vocabulary. Tokens may overlap, so "twone" yields both 2 and 1, but a token
+/// that lies entirely inside an earlier one is skipped: "42" read as a numeral
+/// and "twentyone" read as a compound don't also yield their last part.
+#[derive(Debug, Clone)]
+struct Tokens<'a> {
+    line: &'a str,
+    vocabulary: &'a Vocabulary,
+    position: usize,
+    covered_until: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(line: &'a str, vocabulary: &'a Vocabulary) -> Self {
+        Tokens {
+            line,
+            vocabulary,
+            position: 0,
+            covered_until: 0,
+        }
+    }
 }
 
-fn get_combined_of_line_considering_words(line: String) -> String {
-    let first_word = get_first_word_with_index(&line);
-    let last_word = get_last_word_with_index(&line);
-    let first_digit = get_first_digit_with_index(&line);
-    let last_digit = get_last_digit_with_index(&line);
+impl Iterator for Tokens<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(ch) = self.line[self.position..].chars().next() {
+            let start = self.position;
+            self.position += ch.len_utf8();
+
+            if let Some((value, length)) = self.vocabulary.match_at(&self.line[start..]) {
+                if start + length > self.covered_until {
+                    self.covered_until = start + length;
+                    return Some(value);
+                }
+            }
+        }
+
+        return None;
+    }
+}
+
+impl Vocabulary {
+    /// Only the ASCII digits 1 to 9, as in part 1.
+    pub fn digits() -> Self {
+        Vocabulary {
+            words: vec![],
+            multi_digit_numerals: false,
+            zero: false,
+            compounds: false,
+            digit_mode: DigitMode::Ascii,
+        }
+    }
+
+    /// The digits plus "one" to "nine", as in part 2.
+    pub fn single_digit_words() -> Self {
+        Vocabulary {
+            words: WORDS
+                .iter()
+                .enumerate()
+                .map(|(index, word)| (word.to_string(), index as u64 + 1))
+                .collect(),
+            ..Vocabulary::digits()
+        }
+    }
+
+    /// Every digit including zero, whole numerals, "zero" to "nineteen", the tens
+    /// from "twenty" to "ninety" and compounds such as "twentyone" or "forty-two".
+    pub fn extended() -> Self {
+        let mut words = vec![("zero".to_string(), 0)];
+        words.extend(Vocabulary::single_digit_words().words);
+        words.extend(
+            TEENS
+                .iter()
+                .enumerate()
+                .map(|(index, word)| (word.to_string(), index as u64 + 10)),
+        );
+        words.extend(
+            TENS.iter()
+                .enumerate()
+                .map(|(index, word)| (word.to_string(), (index as u64 + 2) * 10)),
+        );
+
+        Vocabulary {
+            words,
+            multi_digit_numerals: true,
+            zero: true,
+            compounds: true,
+            digit_mode: DigitMode::Ascii,
+        }
+    }
+
+    /// Returns the value and byte length of the longest token `rest` starts with.
+    fn match_at(&self, rest: &str) -> Option<(u64, usize)> {
+        let first = rest.chars().next()?;
+
+        if let Some(digit) = self.digit_mode.digit_value(first) {
+            if digit == 0 && !self.zero {
+                return None;
+            }
+
+            let length = if self.multi_digit_numerals {
+                rest.chars()
+                    .take_while(|ch| self.digit_mode.digit_value(*ch).is_some())
+                    .map(char::len_utf8)
+                    .sum()
+            } else {
+                first.len_utf8()
+            };
+
+            // A run too long for a `u64` is still one token, so none of its
+            // suffixes get read as tokens of their own.
+            let value = rest[..length].chars().try_fold(0u64, |acc, ch| {
+                acc.checked_mul(10)?
+                    .checked_add(self.digit_mode.digit_value(ch)? as u64)
+            });
+
+            return Some((value.unwrap_or(u64::MAX), length));
+        }
+
+        let (value, length) = self.match_word(rest)?;
+
+        if self.compounds && (20..100).contains(&value) && value % 10 == 0 {
+            let after = &rest[length..];
+            let hyphen = if after.starts_with('-') { 1 } else { 0 };
+
+            if let Some((unit, unit_length)) = self.match_word(&after[hyphen..]) {
+                if (1..10).contains(&unit) {
+                    return Some((value + unit, length + hyphen + unit_length));
+                }
+            }
+        }
+
+        return Some((value, length));
+    }
+
+    fn match_word(&self, rest: &str) -> Option<(u64, usize)> {
+        return self
+            .words
+            .iter()
+            .filter(|(word, _)| rest.starts_with(word.as_str()))
+            .max_by_key(|(word, _)| word.len())
+            .map(|(word, value)| (*value, word.len()));
+    }
+}
+
+fn get_combined_of_line_considering_words(line: &str) -> u64 {
+    let first_word = get_first_word_with_index(line);
+    let last_word = get_last_word_with_index(line);
+    let first_digit = get_first_digit_with_index(line);
+    let last_digit = get_last_digit_with_index(line);
 
     let first_digit_to_append = if first_word.1 < first_digit.1 {
         word_to_digit(first_word.0)
     } else {
-        first_digit.0
+        digit_value(first_digit.0)
     };
 
     let last_digit_to_append = if last_word.1 > last_digit.1 {
         word_to_digit(last_word.0)
     } else {
-        last_digit.0
+        digit_value(last_digit.0)
+    };
+
+    return combine_digits(first_digit_to_append, last_digit_to_append);
+}
+
+/// Part 2 with digits recognised according to `mode`. Every position is compared
+/// as a char index, so multi-byte characters before a digit don't skew which
+/// token comes first.
+fn get_combined_of_line_with_digit_mode(line: &str, mode: DigitMode) -> u64 {
+    let first_word = get_first_word_with_index(line);
+    let last_word = get_last_word_with_index(line);
+    let first_word = (first_word.0, to_char_index(line, first_word.1));
+    let last_word = (last_word.0, to_char_index(line, last_word.1));
+    let first_digit = get_first_digit_with_char_index(line, mode);
+    let last_digit = get_last_digit_with_char_index(line, mode);
+
+    let first_digit_to_append = if first_word.1 < first_digit.1 {
+        word_to_digit(first_word.0)
+    } else {
+        digit_value(first_digit.0)
     };
 
-    let combined = format!("{}{}", first_digit_to_append, last_digit_to_append);
+    let last_digit_to_append = if last_word.1 > last_digit.1 {
+        word_to_digit(last_word.0)
+    } else {
+        digit_value(last_digit.0)
+    };
 
-    return combined;
+    return combine_digits(first_digit_to_append, last_digit_to_append);
 }
 
-fn get_combined_of_line_with_only_digits(line: String) -> String {
-    let first_digit = get_first_digit_with_index(&line);
-    let last_digit = get_last_digit_with_index(&line);
+fn get_combined_of_line_with_only_digits(line: &str) -> u64 {
+    let first_digit = get_first_digit_with_index(line);
+    let last_digit = get_last_digit_with_index(line);
 
-    let combined = format!("{}{}", first_digit.0, last_digit.0);
+    return combine_digits(digit_value(first_digit.0), digit_value(last_digit.0));
+}
+
+fn combine_digits(first: u64, last: u64) -> u64 {
+    return first * 10 + last;
+}
+
+fn digit_value(digit: char) -> u64 {
+    return unicode_digit_value(digit).unwrap() as u64;
+}
+
+fn unicode_digit_value(ch: char) -> Option<u32> {
+    let code = ch as u32;
+    let run = UNICODE_DIGIT_ZEROS.partition_point(|zero| *zero <= code);
+    let zero = UNICODE_DIGIT_ZEROS[run.checked_sub(1)?];
+
+    if code - zero < 10 {
+        return Some(code - zero);
+    }
 
-    return combined;
+    return None;
 }
 
-fn word_to_digit(word: String) -> char {
-    match word.as_str() {
-        "one" => '1',
-        "two" => '2',
-        "three" => '3',
-        "four" => '4',
-        "five" => '5',
-        "six" => '6',
-        "seven" => '7',
-        "eight" => '8',
-        "nine" => '9',
+/// Converts a byte index from the `find`-based helpers into a char index,
+/// keeping the `isize::MIN`/`isize::MAX` "not found" markers as they are.
+fn to_char_index(line: &str, byte_index: isize) -> isize {
+    if byte_index < 0 || byte_index == isize::MAX {
+        return byte_index;
+    }
+
+    return line[..byte_index as usize].chars().count() as isize;
+}
+
+fn word_to_digit(word: &str) -> u64 {
+    match word {
+        "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
         _ => unreachable!("should never have gotten here when converting word to digit"),
     }
 }
 
-fn get_first_word_with_index(line: &String) -> WordData {
-    let words = vec![
-        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-    ];
-    let mut first_word = String::new();
+fn get_first_word_with_index(line: &str) -> WordData {
+    let mut first_word = "";
     let mut first_index = isize::MAX;
 
-    for word in words.iter() {
-        if let Some(index) = line.find(*word) {
+    for word in WORDS {
+        if let Some(index) = line.find(word) {
             let index = index as isize;
             if index <= first_index {
-                first_word = word.to_string();
+                first_word = word;
                 first_index = index;
             }
         }
@@ -103,18 +641,15 @@ fn get_first_word_with_index(line: &String) -> WordData {
     return (first_word, first_index);
 }
 
-fn get_last_word_with_index(line: &String) -> WordData {
-    let words = vec![
-        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-    ];
-    let mut last_word = String::new();
+fn get_last_word_with_index(line: &str) -> WordData {
+    let mut last_word = "";
     let mut last_index = isize::MIN;
 
-    for word in words.iter() {
-        if let Some(index) = line.rfind(*word) {
+    for word in WORDS {
+        if let Some(index) = line.rfind(word) {
             let index = index as isize;
             if index >= last_index {
-                last_word = word.to_string();
+                last_word = word;
                 last_index = index;
             }
         }
@@ -123,17 +658,15 @@ fn get_last_word_with_index(line: &String) -> WordData {
     return (last_word, last_index);
 }
 
-fn get_first_digit_with_index(line: &String) -> DigitData {
-    let digits = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
-
+fn get_first_digit_with_index(line: &str) -> DigitData {
     let mut first_digit = '0';
     let mut first_index = isize::MAX;
 
-    for digit in digits {
-        if let Some(index) = line.find(std::char::from_digit(digit as u32, 10).unwrap()) {
+    for digit in DIGITS {
+        if let Some(index) = line.find(digit) {
             let index = index as isize;
             if index <= first_index {
-                first_digit = std::char::from_digit(digit as u32, 10).unwrap();
+                first_digit = digit;
                 first_index = index;
             }
         }
@@ -142,17 +675,15 @@ fn get_first_digit_with_index(line: &String) -> DigitData {
     return (first_digit, first_index);
 }
 
-fn get_last_digit_with_index(line: &String) -> DigitData {
-    let digits = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
-
+fn get_last_digit_with_index(line: &str) -> DigitData {
     let mut last_digit = '0';
     let mut last_index = isize::MIN;
 
-    for digit in digits {
-        if let Some(index) = line.rfind(std::char::from_digit(digit as u32, 10).unwrap()) {
+    for digit in DIGITS {
+        if let Some(index) = line.rfind(digit) {
             let index = index as isize;
             if index >= last_index {
-                last_digit = std::char::from_digit(digit as u32, 10).unwrap();
+                last_digit = digit;
                 last_index = index;
             }
         }
@@ -161,12 +692,35 @@ fn get_last_digit_with_index(line: &String) -> DigitData {
     return (last_digit, last_index);
 }
 
+fn get_first_digit_with_char_index(line: &str, mode: DigitMode) -> DigitData {
+    return line
+        .chars()
+        .enumerate()
+        .find(|(_, ch)| matches!(mode.digit_value(*ch), Some(1..=9)))
+        .map_or(('0', isize::MAX), |(index, ch)| (ch, index as isize));
+}
+
+fn get_last_digit_with_char_index(line: &str, mode: DigitMode) -> DigitData {
+    return line
+        .chars()
+        .enumerate()
+        .filter(|(_, ch)| matches!(mode.digit_value(*ch), Some(1..=9)))
+        .last()
+        .map_or(('0', isize::MIN), |(index, ch)| (ch, index as isize));
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io::Cursor;
+
     use crate::day1::{
-        get_combined_of_line_considering_words, get_combined_of_line_with_only_digits,
-        get_first_digit_with_index, get_first_word_with_index, get_last_digit_with_index,
-        get_last_word_with_index,
+        extract_calibration_value, get_combined_of_line_considering_words,
+        get_combined_of_line_with_digit_mode, get_combined_of_line_with_only_digits,
+        get_first_digit_with_char_index, get_first_digit_with_index, get_first_word_with_index,
+        get_last_digit_with_char_index, get_last_digit_with_index, get_last_word_with_index,
+        split_at_line_boundaries, sum_calibration_values, sum_calibration_values_parallel,
+        unicode_digit_value, CalibrationRule, Combination, DigitMode, MultiDigit, TokenSelection,
+        Vocabulary, UNICODE_DIGIT_ZEROS,
     };
 
     #[test]
@@ -179,7 +733,7 @@ mod tests {
 
         for test in tests {
             let input = test.0.to_string();
-            let expected_result = (test.1 .0.to_string(), test.1 .1);
+            let expected_result = (test.1 .0, test.1 .1);
             assert_eq!(get_first_word_with_index(&input), expected_result);
         }
     }
@@ -195,7 +749,7 @@ mod tests {
 
         for test in tests {
             let input = test.0.to_string();
-            let expected_result = (test.1 .0.to_string(), test.1 .1);
+            let expected_result = (test.1 .0, test.1 .1);
             assert_eq!(get_last_word_with_index(&input), expected_result);
         }
     }
@@ -236,29 +790,307 @@ mod tests {
 
     #[test]
     fn test_get_combined_considering_only_digits() {
-        let tests = vec![("1", "11")];
+        let tests = vec![("1", 11), ("a1b2c3", 13)];
 
         for test in tests {
-            assert_eq!(
-                get_combined_of_line_with_only_digits(test.0.to_string()),
-                test.1.to_string()
-            );
+            assert_eq!(get_combined_of_line_with_only_digits(test.0), test.1);
         }
     }
 
     #[test]
     fn test_get_combined_considering_words() {
         let tests = vec![
-            ("sixthree6lxcrsevenseven69twonegs", "61"),
-            ("1", "11"),
-            ("one", "11"),
+            ("sixthree6lxcrsevenseven69twonegs", 61),
+            ("1", 11),
+            ("one", 11),
         ];
 
         for test in tests {
+            assert_eq!(get_combined_of_line_considering_words(test.0), test.1);
+        }
+    }
+
+    #[test]
+    fn test_sum_calibration_values() {
+        let input = "1abc2\r\npqr3stu8vwx\n\na1b2c3d4e5f\ntreb7uchet";
+
+        assert_eq!(
+            sum_calibration_values(Cursor::new(input), get_combined_of_line_with_only_digits)
+                .unwrap(),
+            142
+        );
+
+        let input = "two1nine\neightwothree\nabcone2threexyz\nxtwone3four\n4nineeightseven2\nzoneight234\n7pqrstsixteen\n";
+
+        assert_eq!(
+            sum_calibration_values(Cursor::new(input), get_combined_of_line_considering_words)
+                .unwrap(),
+            281
+        );
+    }
+
+    #[test]
+    fn test_extract_calibration_value_matches_puzzle_rules() {
+        let lines = vec![
+            "1abc2",
+            "a1b2c3d4e5f",
+            "treb7uchet",
+            "sixthree6lxcrsevenseven69twonegs",
+            "xtwone3four",
+            "one",
+            "",
+        ];
+
+        for line in lines {
+            assert_eq!(
+                extract_calibration_value(line, &CalibrationRule::part_1()),
+                get_combined_of_line_with_only_digits(line)
+            );
+            assert_eq!(
+                extract_calibration_value(line, &CalibrationRule::part_2()),
+                get_combined_of_line_considering_words(line)
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_calibration_value() {
+        let tests = vec![
+            (
+                TokenSelection::Ends { first: 2, last: 1 },
+                Combination::Concatenate,
+                124,
+            ),
+            (
+                TokenSelection::Ends { first: 0, last: 2 },
+                Combination::Concatenate,
+                34,
+            ),
+            (
+                TokenSelection::Ends { first: 1, last: 1 },
+                Combination::Sum,
+                5,
+            ),
+            (TokenSelection::All, Combination::Concatenate, 1234),
+            (TokenSelection::All, Combination::Sum, 10),
+            (TokenSelection::All, Combination::Product, 24),
+        ];
+
+        for (selection, combination, expected) in tests {
+            let rule = CalibrationRule {
+                selection,
+                combination,
+                ..CalibrationRule::part_2()
+            };
+            assert_eq!(extract_calibration_value("1two3xfour", &rule), expected);
+        }
+
+        let rule = CalibrationRule {
+            selection: TokenSelection::Ends { first: 3, last: 3 },
+            ..CalibrationRule::part_1()
+        };
+        assert_eq!(extract_calibration_value("a7b", &rule), 77);
+        assert_eq!(extract_calibration_value("abc", &rule), 0);
+    }
+
+    #[test]
+    fn test_extract_calibration_value_saturates() {
+        let long_line = "9".repeat(23);
+
+        for combination in [Combination::Concatenate, Combination::Product] {
+            let rule = CalibrationRule {
+                selection: TokenSelection::All,
+                combination,
+                ..CalibrationRule::part_1()
+            };
+            assert_eq!(extract_calibration_value(&long_line, &rule), u64::MAX);
+        }
+
+        let rule = CalibrationRule {
+            selection: TokenSelection::All,
+            combination: Combination::Sum,
+            ..CalibrationRule::part_1()
+        };
+        assert_eq!(extract_calibration_value(&long_line, &rule), 9 * 23);
+    }
+
+    #[test]
+    fn test_split_at_line_boundaries() {
+        let input = "ab\ncd\nef";
+
+        assert_eq!(
+            split_at_line_boundaries(input, 1),
+            vec!["ab\n", "cd\n", "ef"]
+        );
+        assert_eq!(split_at_line_boundaries(input, 4), vec!["ab\ncd\n", "ef"]);
+        assert_eq!(split_at_line_boundaries(input, 100), vec!["ab\ncd\nef"]);
+        assert_eq!(
+            split_at_line_boundaries(input, 0),
+            vec!["ab\n", "cd\n", "ef"]
+        );
+        assert!(split_at_line_boundaries("", 4).is_empty());
+    }
+
+    #[test]
+    fn test_sum_calibration_values_parallel() {
+        let input = "two1nine\neightwothree\nabcone2threexyz\nxtwone3four\n4nineeightseven2\nzoneight234\n7pqrstsixteen\n";
+
+        for threads in [1, 2, 8] {
+            for chunk_size in [1, 7, 1024] {
+                assert_eq!(
+                    sum_calibration_values_parallel(
+                        input,
+                        threads,
+                        chunk_size,
+                        get_combined_of_line_considering_words
+                    ),
+                    281
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_extract_calibration_value_with_extended_vocabulary() {
+        let rule = CalibrationRule {
+            vocabulary: Vocabulary::extended(),
+            ..CalibrationRule::part_1()
+        };
+
+        let tests = vec![
+            ("zero", 0),
+            ("a0b", 0),
+            ("ten", 1010),
+            ("x42y7", 427),
+            ("sixteenfive", 165),
+            ("twentyone", 2121),
+            ("forty-twoxeightwo", 422),
+            ("twentyxone", 201),
+            ("ninetynine", 9999),
+            ("seventeen", 1717),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                extract_calibration_value(input, &rule),
+                expected,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_calibration_value_multi_digit_contributions() {
+        let tests = vec![
+            (MultiDigit::Whole, Combination::Concatenate, 1245),
+            (MultiDigit::LeadingDigit, Combination::Concatenate, 14),
+            (MultiDigit::TrailingDigit, Combination::Concatenate, 25),
+            (MultiDigit::Whole, Combination::Sum, 57),
+            (MultiDigit::Whole, Combination::Product, 540),
+        ];
+
+        for (multi_digit, combination, expected) in tests {
+            let rule = CalibrationRule {
+                combination,
+                multi_digit,
+                vocabulary: Vocabulary::extended(),
+                ..CalibrationRule::part_1()
+            };
+            assert_eq!(extract_calibration_value("a12bc45", &rule), expected);
+        }
+    }
+
+    #[test]
+    fn test_overlong_numeral_is_one_token() {
+        let vocabulary = Vocabulary::extended();
+        let line = format!("a{}b7", "9".repeat(25));
+
+        assert_eq!(
+            super::Tokens::new(&line, &vocabulary).collect::<Vec<_>>(),
+            vec![u64::MAX, 7]
+        );
+    }
+
+    #[test]
+    fn test_unicode_digit_value() {
+        for zero in UNICODE_DIGIT_ZEROS {
+            for value in 0..10 {
+                let ch = char::from_u32(zero + value).unwrap();
+                assert!(ch.is_numeric());
+                assert_eq!(unicode_digit_value(ch), Some(value));
+            }
+        }
+
+        assert_eq!(unicode_digit_value('7'), Some(7));
+        assert_eq!(unicode_digit_value('٣'), Some(3));
+        assert_eq!(unicode_digit_value('３'), Some(3));
+        // Kawi and Nag Mundari, both added in Unicode 15.
+        assert_eq!(unicode_digit_value('\u{11F53}'), Some(3));
+        assert_eq!(unicode_digit_value('\u{1E4F9}'), Some(9));
+        assert_eq!(unicode_digit_value('a'), None);
+        assert_eq!(unicode_digit_value('½'), None);
+        assert_eq!(unicode_digit_value('\u{0}'), None);
+    }
+
+    #[test]
+    fn test_get_digit_with_char_index() {
+        let tests = vec![
+            ("ab٣cd٧", DigitMode::Unicode, ('٣', 2), ('٧', 5)),
+            (
+                "ab٣cd٧",
+                DigitMode::Ascii,
+                ('0', isize::MAX),
+                ('0', isize::MIN),
+            ),
+            ("é5ñ6", DigitMode::Ascii, ('5', 1), ('6', 3)),
+            ("१x２٠", DigitMode::Unicode, ('१', 0), ('２', 2)),
+            (
+                "naïve",
+                DigitMode::Unicode,
+                ('0', isize::MAX),
+                ('0', isize::MIN),
+            ),
+        ];
+
+        for (input, mode, first, last) in tests {
+            assert_eq!(get_first_digit_with_char_index(input, mode), first);
+            assert_eq!(get_last_digit_with_char_index(input, mode), last);
+        }
+    }
+
+    #[test]
+    fn test_get_combined_of_line_with_digit_mode() {
+        let tests = vec![
+            ("ñone٤", DigitMode::Unicode, 14),
+            ("ñone٤", DigitMode::Ascii, 11),
+            ("٣twoé", DigitMode::Unicode, 32),
+            ("ｘ１２ｙ", DigitMode::Unicode, 12),
+            ("ééé9ééfive", DigitMode::Ascii, 95),
+            ("sixthree6lxcrsevenseven69twonegs", DigitMode::Ascii, 61),
+        ];
+
+        for (input, mode, expected) in tests {
             assert_eq!(
-                get_combined_of_line_considering_words(test.0.to_string()),
-                test.1.to_string()
+                get_combined_of_line_with_digit_mode(input, mode),
+                expected,
+                "{}",
+                input
             );
         }
     }
+
+    #[test]
+    fn test_extract_calibration_value_with_unicode_digits() {
+        let mut vocabulary = Vocabulary::extended();
+        vocabulary.digit_mode = DigitMode::Unicode;
+        let rule = CalibrationRule {
+            vocabulary,
+            ..CalibrationRule::part_1()
+        };
+
+        assert_eq!(extract_calibration_value("x١٢yसात", &rule), 1212);
+        assert_eq!(extract_calibration_value("٠ten", &rule), 10);
+        assert_eq!(extract_calibration_value("ａ７ｂtwo", &rule), 72);
+    }
 }
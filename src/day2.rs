@@ -1,21 +1,215 @@
-#[derive(Debug, PartialEq)]
+mod inference;
+mod optimizer;
+mod query;
+mod serialization;
+mod stats;
+mod stream;
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, Read},
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct Game {
     id: usize,
     reveals: Vec<Reveal>,
 }
 
-#[derive(Debug, PartialEq, Default)]
-struct Reveal {
-    red: usize,
-    green: usize,
-    blue: usize,
+/// An interned cube colour. Names are leaked once and shared afterwards, so
+/// colours stay `Copy` and cheap to compare whatever the input calls them.
+/// The leaked names are never freed: memory grows with the number of distinct
+/// names seen over the life of the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Color(&'static str);
+
+impl Color {
+    fn new(name: &str) -> Self {
+        static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+        let mut names = NAMES.get_or_init(Default::default).lock().unwrap();
+        if let Some(interned) = names.get(name) {
+            return Color(interned);
+        }
+
+        let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+        names.insert(interned);
+
+        return Color(interned);
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_str(self.0);
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = normalize_color_name(&String::deserialize(deserializer)?);
+        if name.is_empty() {
+            return Err(serde::de::Error::custom("empty colour name"));
+        }
+
+        return Ok(Color::new(&name));
+    }
+}
+
+/// Cube counts keyed by colour. Colours without cubes are never stored, so
+/// missing and zero counts compare the same.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(into = "BTreeMap<Color, usize>", try_from = "BTreeMap<String, usize>")]
+struct CubeCounts {
+    counts: BTreeMap<Color, usize>,
+}
+
+impl CubeCounts {
+    fn get(&self, color: Color) -> usize {
+        return self.counts.get(&color).copied().unwrap_or(0);
+    }
+
+    fn set(&mut self, color: Color, amount: usize) {
+        if amount == 0 {
+            self.counts.remove(&color);
+        } else {
+            self.counts.insert(color, amount);
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (Color, usize)> + '_ {
+        return self.counts.iter().map(|(color, amount)| (*color, *amount));
+    }
+
+    /// Whether every colour here has at most as many cubes as in `bag`.
+    fn fits_in(&self, bag: &CubeCounts) -> bool {
+        return self.iter().all(|(color, amount)| amount <= bag.get(color));
+    }
+
+    /// The product of the counts of every colour present, saturating at
+    /// `usize::MAX`. Colours that never showed up don't take part, and an
+    /// empty set has no power at all.
+    fn power(&self) -> usize {
+        if self.counts.is_empty() {
+            return 0;
+        }
+
+        return self.counts.values().copied().fold(1, usize::saturating_mul);
+    }
+}
+
+impl<const N: usize> From<[(&str, usize); N]> for CubeCounts {
+    fn from(counts: [(&str, usize); N]) -> Self {
+        let mut cube_counts = CubeCounts::default();
+        for (name, amount) in counts {
+            cube_counts.set(Color::new(name), amount);
+        }
+
+        return cube_counts;
+    }
+}
+
+impl From<BTreeMap<Color, usize>> for CubeCounts {
+    fn from(counts: BTreeMap<Color, usize>) -> Self {
+        let mut cube_counts = CubeCounts::default();
+        for (color, amount) in counts {
+            cube_counts.set(color, amount);
+        }
+
+        return cube_counts;
+    }
+}
+
+/// Normalises colour names like the text parser does, adding up the counts of
+/// names that turn out to be the same colour, such as "Red" and "reds".
+impl TryFrom<BTreeMap<String, usize>> for CubeCounts {
+    type Error = String;
+
+    fn try_from(counts: BTreeMap<String, usize>) -> Result<Self, Self::Error> {
+        let mut cube_counts = CubeCounts::default();
+        for (name, amount) in counts {
+            let name = normalize_color_name(&name);
+            if name.is_empty() {
+                return Err("empty colour name".to_string());
+            }
+
+            let color = Color::new(&name);
+            let total = cube_counts
+                .get(color)
+                .checked_add(amount)
+                .ok_or_else(|| ParseGameError::TooManyCubes(name).to_string())?;
+            cube_counts.set(color, total);
+        }
+
+        return Ok(cube_counts);
+    }
+}
+
+impl From<CubeCounts> for BTreeMap<Color, usize> {
+    fn from(cube_counts: CubeCounts) -> Self {
+        return cube_counts.counts;
+    }
 }
 
-#[derive(Debug, PartialEq, Default)]
-struct GameAmounts {
-    red: usize,
-    green: usize,
-    blue: usize,
+impl fmt::Display for CubeCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<_> = self
+            .iter()
+            .map(|(color, amount)| format!("{} {}", amount, color.0))
+            .collect();
+
+        return write!(f, "{}", parts.join(", "));
+    }
+}
+
+type Reveal = CubeCounts;
+type GameAmounts = CubeCounts;
+
+/// Answers "which games could have been played with this bag" for many bags
+/// over the same games. Each game's minimum amounts are computed once up front,
+/// so a query only compares counts.
+struct BagQueryEngine {
+    game_amounts: Vec<(usize, GameAmounts)>,
+}
+
+#[derive(Debug, PartialEq)]
+struct BagQueryResult {
+    feasible_ids: Vec<usize>,
+    /// Saturates at `usize::MAX` rather than overflowing.
+    id_sum: usize,
+}
+
+impl BagQueryEngine {
+    fn new(games: &[Game]) -> Self {
+        BagQueryEngine {
+            game_amounts: games
+                .iter()
+                .map(|game| (game.id, get_game_amount(game)))
+                .collect(),
+        }
+    }
+
+    fn query(&self, bag: &CubeCounts) -> BagQueryResult {
+        let feasible_ids: Vec<_> = self
+            .game_amounts
+            .iter()
+            .filter(|(_, amounts)| amounts.fits_in(bag))
+            .map(|(id, _)| *id)
+            .collect();
+        let id_sum = feasible_ids.iter().copied().fold(0, usize::saturating_add);
+
+        return BagQueryResult {
+            feasible_ids,
+            id_sum,
+        };
+    }
 }
 
 #[allow(dead_code)]
@@ -23,149 +217,481 @@ pub fn run() {
     run_part_2();
 }
 
-#[allow(dead_code)]
-fn run_part_2() {
-    let contents = std::fs::read_to_string("input/day2.txt").unwrap();
+pub fn run_command(args: &[String]) {
+    run_with_options(args, &ParseOptions::default());
+}
 
-    let games = parse_all_lines(contents);
-    let mut amounts = vec![];
-    for game in games {
-        let game_amounts = get_game_amount(game);
+/// `--reject-duplicates` in front of any command makes a colour repeated
+/// within one reveal an error instead of adding the counts up.
+fn run_with_options(args: &[String], options: &ParseOptions) {
+    match args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>()[..] {
+        ["--reject-duplicates", ..] => run_with_options(
+            &args[1..],
+            &ParseOptions {
+                duplicates: DuplicatePolicy::Reject,
+            },
+        ),
+        ["bags", "--file", path] => {
+            let contents = std::fs::read_to_string(path).unwrap();
+            run_bags(
+                contents.lines().filter(|line| !line.trim().is_empty()),
+                options,
+            );
+        }
+        ["bags", ref bags @ ..] if !bags.is_empty() => run_bags(bags.iter().copied(), options),
+        ["minimal-bag", selection] => run_minimal_bag(selection, options),
+        ["budget", budget] => run_budget(budget.parse().expect("budget must be a number"), options),
+        ["frontier"] => run_frontier(options),
+        ["export", "json"] => print!("{}", serialization::to_json(&read_games(options))),
+        ["export", "csv"] => print!("{}", serialization::to_csv(&read_games(options))),
+        ["import", path] => run_import(path, options),
+        ["infer", id] => run_infer(
+            id.parse().expect("game id must be a number"),
+            20,
+            5,
+            options,
+        ),
+        ["infer", id, max_per_color, top] => run_infer(
+            id.parse().expect("game id must be a number"),
+            max_per_color
+                .parse()
+                .expect("max per colour must be a number"),
+            top.parse().expect("top must be a number"),
+            options,
+        ),
+        ["stream"] => run_stream(None, None, options),
+        ["stream", path] => run_stream(Some(path), None, options),
+        ["follow", path] => run_stream(Some(path), Some(Duration::from_millis(250)), options),
+        ["query", query] => run_query(query, options),
+        ["stats"] => run_stats(false, options),
+        ["stats", "--json"] => run_stats(true, options),
+        _ => run(),
+    }
+}
 
-        let (mut max_red, mut max_green, mut max_blue) = (0, 0, 0);
+/// Prints the feasible games of every bag, each written like a reveal
+/// ("12 red, 13 green, 14 blue").
+fn run_bags<'a>(bags: impl Iterator<Item = &'a str>, options: &ParseOptions) {
+    let engine = BagQueryEngine::new(&read_games(options));
+
+    for bag in bags {
+        let bag = parse_reveal_set(bag).unwrap_or_else(|error| panic!("invalid bag: {}", error));
+        let result = engine.query(&bag);
+
+        println!(
+            "{}: {} games, sum {}, ids {:?}",
+            bag,
+            result.feasible_ids.len(),
+            result.id_sum,
+            result.feasible_ids
+        );
+    }
+}
 
-        if game_amounts.red > max_red {
-            max_red = game_amounts.red;
-            max_green = game_amounts.green;
-            max_blue = game_amounts.blue;
-        }
+fn run_minimal_bag(selection: &str, options: &ParseOptions) {
+    let ids = optimizer::parse_id_selection(selection)
+        .unwrap_or_else(|part| panic!("invalid game id selection \"{}\"", part));
+
+    let ids: HashSet<usize> = ids.into_iter().collect();
+
+    let games = read_games(options);
+    let bag = optimizer::get_minimal_bag(games.iter().filter(|game| ids.contains(&game.id)));
 
-        amounts.push(max_red * max_green * max_blue);
+    println!("smallest bag: {}", bag);
+}
+
+fn run_budget(budget: usize, options: &ParseOptions) {
+    let choice = optimizer::get_max_games_within_budget(&read_games(options), budget)
+        .unwrap_or_else(|error| panic!("cannot search budgets: {}", error));
+
+    println!(
+        "{} ({} cubes) admits {} games: {:?}",
+        choice.bag,
+        choice.total_cubes(),
+        choice.game_ids.len(),
+        choice.game_ids
+    );
+}
+
+fn run_frontier(options: &ParseOptions) {
+    let frontier = optimizer::get_budget_frontier(&read_games(options))
+        .unwrap_or_else(|error| panic!("cannot search budgets: {}", error));
+
+    for choice in frontier {
+        println!(
+            "{:>4} cubes, {:>3} games: {}",
+            choice.total_cubes(),
+            choice.game_ids.len(),
+            choice.bag
+        );
     }
+}
+
+/// Solves both parts for the games in `path`, which may be JSON, CSV or the
+/// puzzle text format.
+fn run_import(path: &str, options: &ParseOptions) {
+    let games = serialization::read_games_from(path, options)
+        .unwrap_or_else(|error| panic!("failed to read {}: {}", path, error));
+
+    let result = BagQueryEngine::new(&games).query(&get_puzzle_bag());
+
+    println!("games: {}", games.len());
+    println!("part 1 sum: {}", result.id_sum);
+    println!("part 2 sum: {}", get_sum_of_powers(&games));
+}
+
+/// Prints the `top` most plausible bags for game `id`.
+fn run_infer(id: usize, max_per_color: usize, top: usize, options: &ParseOptions) {
+    let games = read_games(options);
+    let game = games
+        .iter()
+        .find(|game| game.id == id)
+        .unwrap_or_else(|| panic!("no game with id {}", id));
+
+    let estimates = inference::infer_bags(game, max_per_color)
+        .unwrap_or_else(|error| panic!("cannot infer bags: {}", error));
+
+    for estimate in estimates.iter().take(top) {
+        println!(
+            "{:.4} (log-likelihood {:.3}): {}",
+            estimate.posterior, estimate.log_likelihood, estimate.bag
+        );
+    }
+}
+
+/// Prints running totals for every game read from `path`, or from stdin
+/// without one. With `follow`, keeps polling the file for appended games.
+fn run_stream(path: Option<&str>, follow: Option<Duration>, options: &ParseOptions) {
+    let reader: Box<dyn Read> = match path {
+        Some(path) => Box::new(File::open(path).unwrap()),
+        None => Box::new(io::stdin()),
+    };
+    let mut evaluator = stream::GameLogEvaluator::new(get_puzzle_bag(), *options);
+
+    stream::evaluate_game_log(
+        reader,
+        &mut evaluator,
+        follow,
+        |totals, result| match result {
+            Ok(id) => println!(
+                "game {}: {} games, part 1 sum {}, part 2 sum {}",
+                id, totals.games, totals.feasible_id_sum, totals.power_sum
+            ),
+            Err(error) => eprintln!("{}", error),
+        },
+    )
+    .unwrap();
+
+    let totals = evaluator.totals();
+    println!(
+        "total: {} games, part 1 sum {}, part 2 sum {}",
+        totals.games, totals.feasible_id_sum, totals.power_sum
+    );
+}
+
+fn run_query(query: &str, options: &ParseOptions) {
+    let query: query::Query = query
+        .parse()
+        .unwrap_or_else(|error| panic!("invalid query: {}", error));
+
+    let ids = query
+        .find_matching(&read_games(options))
+        .unwrap_or_else(|error| panic!("cannot evaluate query: {}", error));
+
+    println!(
+        "{} games, sum {}, ids {:?}",
+        ids.len(),
+        ids.iter().sum::<usize>(),
+        ids
+    );
+}
+
+fn run_stats(json: bool, options: &ParseOptions) {
+    let stats = stats::get_stats(&read_games(options), &get_puzzle_bag());
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats).unwrap());
+    } else {
+        print!("{}", stats);
+    }
+}
+
+#[allow(dead_code)]
+fn run_part_2() {
+    let games = read_games(&ParseOptions::default());
 
-    println!("sum: {}", amounts.iter().sum::<usize>());
+    println!("sum: {}", get_sum_of_powers(&games));
 }
 
 #[allow(dead_code)]
 fn run_part_1() {
-    let contents = std::fs::read_to_string("input/day2.txt").unwrap();
+    let games = read_games(&ParseOptions::default());
+    let result = BagQueryEngine::new(&games).query(&get_puzzle_bag());
 
-    let games = parse_all_lines(contents);
-    let mut possibles = vec![];
-    for game in games {
-        let game_id = game.id;
-        let game_amounts = get_game_amount(game);
-        if game_amounts.red <= 12 && game_amounts.green <= 13 && game_amounts.blue <= 14 {
-            possibles.push(game_id);
-        }
+    println!("sum: {}", result.id_sum);
+}
+
+/// The bag part 1 asks about.
+fn get_puzzle_bag() -> CubeCounts {
+    return CubeCounts::from([("red", 12), ("green", 13), ("blue", 14)]);
+}
+
+/// Sums the power of the minimum bag of every game, saturating at
+/// `usize::MAX`.
+fn get_sum_of_powers(games: &[Game]) -> usize {
+    return games
+        .iter()
+        .map(|game| game.minimum_bag().power())
+        .fold(0, usize::saturating_add);
+}
+
+impl Game {
+    /// The smallest bag this game could have been played with.
+    fn minimum_bag(&self) -> GameAmounts {
+        return get_game_amount(self);
     }
-    println!("sum: {}", possibles.iter().sum::<usize>());
 }
 
-fn get_game_amount(game: Game) -> GameAmounts {
+fn get_game_amount(game: &Game) -> GameAmounts {
     let mut game_amounts = GameAmounts::default();
 
-    for reveal in game.reveals {
-        if game_amounts.red < reveal.red {
-            game_amounts.red = reveal.red;
-        }
-        if game_amounts.green < reveal.green {
-            game_amounts.green = reveal.green;
-        }
-        if game_amounts.blue < reveal.blue {
-            game_amounts.blue = reveal.blue;
+    for reveal in &game.reveals {
+        for (color, amount) in reveal.iter() {
+            if game_amounts.get(color) < amount {
+                game_amounts.set(color, amount);
+            }
         }
     }
 
     return game_amounts;
 }
 
-fn parse_all_lines(contents: impl Into<String>) -> Vec<Game> {
-    let contents: String = contents.into();
+/// What to do when a colour shows up more than once in the same reveal.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum DuplicatePolicy {
+    /// "1 red, 2 red" counts as 3 red.
+    #[default]
+    Sum,
+    Reject,
+}
 
-    return contents.lines().map(parse_line).collect();
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct ParseOptions {
+    duplicates: DuplicatePolicy,
 }
 
-fn parse_line(line: impl Into<String>) -> Game {
-    let line = line.into();
+#[derive(Debug, PartialEq)]
+enum ParseGameError {
+    /// The line doesn't start with "Game <id>:".
+    MissingHeader(String),
+    InvalidGameId(String),
+    InvalidAmount(String),
+    MissingColor(String),
+    DuplicateColor(String),
+    /// A colour's counts within one reveal add up past `usize::MAX`.
+    TooManyCubes(String),
+}
 
-    let parts: Vec<_> = line.split(": ").collect();
-    let (game_info, game_data) = match parts[..] {
-        [game_info, game_data] => (game_info, game_data),
-        _ => unreachable!(),
-    };
+impl fmt::Display for ParseGameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ParseGameError::MissingHeader(line) => {
+                write!(f, "expected \"Game <id>: ...\", got \"{}\"", line)
+            }
+            ParseGameError::InvalidGameId(id) => write!(f, "invalid game id \"{}\"", id),
+            ParseGameError::InvalidAmount(amount) => write!(f, "invalid amount \"{}\"", amount),
+            ParseGameError::MissingColor(item) => write!(f, "missing colour in \"{}\"", item),
+            ParseGameError::DuplicateColor(color) => {
+                write!(f, "colour \"{}\" appears twice in one reveal", color)
+            }
+            ParseGameError::TooManyCubes(color) => {
+                write!(f, "too many \"{}\" cubes in one reveal", color)
+            }
+        };
+    }
+}
 
-    let game_id: usize = game_info
-        .replace("Game ", "")
-        .parse()
-        .expect(format!("failed to parse game id. Was \"{}\"", game_info).as_str());
+impl Error for ParseGameError {}
 
-    let mut reveals = vec![];
+/// A `ParseGameError` along with the (1-based) line it happened on.
+#[derive(Debug, PartialEq)]
+struct LineError {
+    line: usize,
+    error: ParseGameError,
+}
 
-    for reveal_set in game_data.split("; ") {
-        let reveal = parse_reveal_set(reveal_set);
-        reveals.push(reveal);
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "line {}: {}", self.line, self.error);
     }
+}
 
-    let game = Game {
-        id: game_id,
-        reveals,
+impl Error for LineError {}
+
+fn read_games(options: &ParseOptions) -> Vec<Game> {
+    let contents = std::fs::read_to_string("input/day2.txt").unwrap();
+
+    return parse_all_lines_with(&contents, options).unwrap_or_else(|error| panic!("{}", error));
+}
+
+#[cfg(test)]
+fn parse_all_lines(contents: impl Into<String>) -> Result<Vec<Game>, LineError> {
+    return parse_all_lines_with(&contents.into(), &ParseOptions::default());
+}
+
+/// Parses every non-blank line of `contents` as a game.
+fn parse_all_lines_with(contents: &str, options: &ParseOptions) -> Result<Vec<Game>, LineError> {
+    return contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            parse_line_with(line, options).map_err(|error| LineError {
+                line: index + 1,
+                error,
+            })
+        })
+        .collect();
+}
+
+#[cfg(test)]
+fn parse_line(line: impl Into<String>) -> Result<Game, ParseGameError> {
+    return parse_line_with(&line.into(), &ParseOptions::default());
+}
+
+/// Parses a "Game <id>: <reveal>; <reveal>; ..." line. Whitespace around every
+/// token is free-form and empty reveals or items left by stray separators are
+/// skipped.
+fn parse_line_with(line: &str, options: &ParseOptions) -> Result<Game, ParseGameError> {
+    let (game_info, game_data) = line
+        .split_once(':')
+        .ok_or_else(|| ParseGameError::MissingHeader(line.to_string()))?;
+
+    let game_id = match game_info.split_whitespace().collect::<Vec<_>>()[..] {
+        [game, id] if game.eq_ignore_ascii_case("game") => id
+            .parse()
+            .map_err(|_| ParseGameError::InvalidGameId(id.to_string()))?,
+        _ => return Err(ParseGameError::MissingHeader(line.to_string())),
     };
 
-    return game;
+    let reveals = game_data
+        .split(';')
+        .filter(|reveal_set| !reveal_set.trim().is_empty())
+        .map(|reveal_set| parse_reveal_set_with(reveal_set, options))
+        .collect::<Result<_, _>>()?;
+
+    return Ok(Game {
+        id: game_id,
+        reveals,
+    });
 }
 
-fn parse_reveal_set(reveal_set: impl Into<String>) -> Reveal {
-    let reveal_set = reveal_set.into();
+fn parse_reveal_set(reveal_set: impl Into<String>) -> Result<Reveal, ParseGameError> {
+    return parse_reveal_set_with(&reveal_set.into(), &ParseOptions::default());
+}
 
+/// Parses "<amount> <colour>, <amount> <colour>, ...". Colour names are
+/// lowercased, may span several words and lose a plural "s", so "2 Blues" and
+/// "1 blue" are the same colour.
+fn parse_reveal_set_with(
+    reveal_set: &str,
+    options: &ParseOptions,
+) -> Result<Reveal, ParseGameError> {
     let mut reveal = Reveal::default();
+    let mut seen = HashSet::new();
 
-    for reveal_info in reveal_set.split(", ") {
-        match reveal_info.split(" ").collect::<Vec<_>>()[..] {
-            [amount, color] => match color {
-                "red" => reveal.red = amount.parse().unwrap(),
-                "green" => reveal.green = amount.parse().unwrap(),
-                "blue" => reveal.blue = amount.parse().unwrap(),
-                _ => unreachable!(),
-            },
-            _ => unreachable!(),
+    for reveal_info in reveal_set.split(',') {
+        let mut tokens = reveal_info.split_whitespace();
+
+        let Some(amount) = tokens.next() else {
+            continue;
+        };
+        let amount: usize = amount
+            .parse()
+            .map_err(|_| ParseGameError::InvalidAmount(amount.to_string()))?;
+
+        let color = normalize_color_name(&tokens.collect::<Vec<_>>().join(" "));
+        if color.is_empty() {
+            return Err(ParseGameError::MissingColor(reveal_info.trim().to_string()));
         }
+        let color = Color::new(&color);
+
+        if !seen.insert(color) && options.duplicates == DuplicatePolicy::Reject {
+            return Err(ParseGameError::DuplicateColor(color.0.to_string()));
+        }
+
+        let total = reveal
+            .get(color)
+            .checked_add(amount)
+            .ok_or_else(|| ParseGameError::TooManyCubes(color.0.to_string()))?;
+        reveal.set(color, total);
     }
 
-    return reveal;
+    return Ok(reveal);
+}
+
+/// Puts a colour name in the form the parser uses: lowercase, words separated
+/// by single spaces and without a plural "s".
+fn normalize_color_name(name: &str) -> String {
+    let name = name
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    return singular_color_name(&name).to_string();
+}
+
+/// Drops a plural "s", leaving names that end in "ss" alone. This is only a
+/// heuristic: a singular name ending in "s", such as "lotus", loses its "s"
+/// too, consistently everywhere, so it still names the same colour.
+fn singular_color_name(color: &str) -> &str {
+    if color.ends_with('s') && !color.ends_with("ss") {
+        return &color[..color.len() - 1];
+    }
+
+    return color;
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day2::{get_game_amount, parse_line, parse_reveal_set, Game, GameAmounts};
+    use indoc::indoc;
+
+    use crate::day2::{
+        get_game_amount, get_sum_of_powers, parse_all_lines, parse_all_lines_with, parse_line,
+        parse_line_with, parse_reveal_set, BagQueryEngine, BagQueryResult, Color, CubeCounts,
+        DuplicatePolicy, Game, GameAmounts, LineError, ParseGameError, ParseOptions,
+    };
 
     use super::Reveal;
 
+    /// The example from the puzzle text, shared by the tests of every day 2
+    /// module.
+    pub(super) const EXAMPLE: &str = indoc! {"
+            Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+            Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+            Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+            Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+            Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+    "};
+
+    pub(super) fn example_games() -> Vec<Game> {
+        return parse_all_lines(EXAMPLE).unwrap();
+    }
+
     #[test]
     fn test_get_game_amount() {
         let game = Game {
             id: 20,
             reveals: vec![
-                Reveal {
-                    red: 4,
-                    green: 0,
-                    blue: 3,
-                },
-                Reveal {
-                    red: 1,
-                    green: 2,
-                    blue: 6,
-                },
+                Reveal::from([("red", 4), ("green", 0), ("blue", 3)]),
+                Reveal::from([("red", 1), ("green", 2), ("blue", 6)]),
             ],
         };
 
         assert_eq!(
-            get_game_amount(game),
-            GameAmounts {
-                red: 4,
-                green: 2,
-                blue: 6
-            }
+            get_game_amount(&game),
+            GameAmounts::from([("red", 4), ("green", 2), ("blue", 6)])
         )
     }
 
@@ -174,32 +700,20 @@ mod tests {
         let tests = vec![
             (
                 "3 blue, 4 red",
-                Reveal {
-                    red: 4,
-                    green: 0,
-                    blue: 3,
-                },
+                Reveal::from([("red", 4), ("green", 0), ("blue", 3)]),
             ),
             (
                 "1 red, 2 green, 6 blue",
-                Reveal {
-                    red: 1,
-                    green: 2,
-                    blue: 6,
-                },
+                Reveal::from([("red", 1), ("green", 2), ("blue", 6)]),
             ),
             (
                 "2 green",
-                Reveal {
-                    red: 0,
-                    green: 2,
-                    blue: 0,
-                },
+                Reveal::from([("red", 0), ("green", 2), ("blue", 0)]),
             ),
         ];
 
         for (input, expected) in tests {
-            let actual = parse_reveal_set(input.to_string());
+            let actual = parse_reveal_set(input.to_string()).unwrap();
             assert_eq!(actual, expected);
         }
     }
@@ -207,27 +721,290 @@ mod tests {
     #[test]
     fn test_parse_game() {
         assert_eq!(
-            parse_line("Game 20: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green"),
+            parse_line("Game 20: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green").unwrap(),
             Game {
                 id: 20,
                 reveals: vec![
-                    Reveal {
-                        red: 4,
-                        green: 0,
-                        blue: 3,
-                    },
-                    Reveal {
-                        red: 1,
-                        green: 2,
-                        blue: 6,
-                    },
-                    Reveal {
-                        red: 0,
-                        green: 2,
-                        blue: 0,
-                    }
+                    Reveal::from([("red", 4), ("green", 0), ("blue", 3)]),
+                    Reveal::from([("red", 1), ("green", 2), ("blue", 6)]),
+                    Reveal::from([("red", 0), ("green", 2), ("blue", 0)])
                 ],
             }
         )
     }
+
+    #[test]
+    fn test_parse_game_with_any_colors() {
+        let game = parse_line("Game 3: 2 yellow, 1 red; 5 yellow, 3 magenta").unwrap();
+
+        assert_eq!(
+            game.reveals,
+            vec![
+                Reveal::from([("yellow", 2), ("red", 1)]),
+                Reveal::from([("yellow", 5), ("magenta", 3)]),
+            ]
+        );
+        assert_eq!(
+            get_game_amount(&game),
+            GameAmounts::from([("yellow", 5), ("red", 1), ("magenta", 3)])
+        );
+    }
+
+    #[test]
+    fn test_fits_in() {
+        let amounts = GameAmounts::from([("red", 3), ("yellow", 2)]);
+
+        assert!(amounts.fits_in(&CubeCounts::from([("red", 3), ("yellow", 2), ("blue", 1)])));
+        assert!(!amounts.fits_in(&CubeCounts::from([("red", 3), ("yellow", 1)])));
+        assert!(!amounts.fits_in(&CubeCounts::from([("red", 12), ("green", 13)])));
+        assert!(GameAmounts::default().fits_in(&CubeCounts::default()));
+    }
+
+    #[test]
+    fn test_power() {
+        let tests = vec![
+            (
+                GameAmounts::from([("red", 4), ("green", 2), ("blue", 6)]),
+                48,
+            ),
+            (
+                GameAmounts::from([("red", 4), ("green", 2), ("yellow", 6)]),
+                48,
+            ),
+            (GameAmounts::from([("green", 2), ("blue", 6)]), 12),
+            (GameAmounts::from([("red", 5)]), 5),
+            (GameAmounts::default(), 0),
+        ];
+
+        for (amounts, expected) in tests {
+            assert_eq!(amounts.power(), expected);
+        }
+    }
+
+    #[test]
+    fn test_minimum_bag_of_games_missing_a_color() {
+        let tests = vec![
+            (
+                "Game 1: 3 blue, 4 red; 2 blue",
+                GameAmounts::from([("red", 4), ("blue", 3)]),
+                12,
+            ),
+            (
+                "Game 2: 2 green; 3 blue, 1 green",
+                GameAmounts::from([("green", 2), ("blue", 3)]),
+                6,
+            ),
+            (
+                "Game 3: 1 red, 7 green, 2 blue; 3 red",
+                GameAmounts::from([("red", 3), ("green", 7), ("blue", 2)]),
+                42,
+            ),
+        ];
+
+        for (input, expected_bag, expected_power) in tests {
+            let game = parse_line(input).unwrap();
+            assert_eq!(game.minimum_bag(), expected_bag);
+            assert_eq!(game.minimum_bag().power(), expected_power);
+        }
+    }
+
+    #[test]
+    fn test_get_sum_of_powers() {
+        assert_eq!(get_sum_of_powers(&example_games()), 2286);
+
+        let huge = parse_all_lines(indoc! {"
+            Game 1: 4294967296 red, 4294967296 blue
+            Game 2: 1 red
+        "})
+        .unwrap();
+        assert_eq!(huge[0].minimum_bag().power(), usize::MAX);
+        assert_eq!(get_sum_of_powers(&huge), usize::MAX);
+    }
+
+    #[test]
+    fn test_color_interning() {
+        assert_eq!(
+            Color::new("teal"),
+            Color::new(String::from("teal").as_str())
+        );
+        assert!(std::ptr::eq(Color::new("teal").0, Color::new("teal").0));
+        assert_ne!(Color::new("teal"), Color::new("red"));
+    }
+
+    #[test]
+    fn test_bag_query_engine() {
+        let games = example_games();
+        let engine = BagQueryEngine::new(&games);
+
+        assert_eq!(
+            engine.query(&CubeCounts::from([
+                ("red", 12),
+                ("green", 13),
+                ("blue", 14)
+            ])),
+            BagQueryResult {
+                feasible_ids: vec![1, 2, 5],
+                id_sum: 8,
+            }
+        );
+        assert_eq!(
+            engine.query(&CubeCounts::from([("red", 4), ("green", 3), ("blue", 6)])),
+            BagQueryResult {
+                feasible_ids: vec![1, 2],
+                id_sum: 3,
+            }
+        );
+        assert_eq!(
+            engine.query(&CubeCounts::default()),
+            BagQueryResult {
+                feasible_ids: vec![],
+                id_sum: 0,
+            }
+        );
+
+        let huge_ids = [usize::MAX, 1].map(|id| Game {
+            id,
+            reveals: vec![],
+        });
+        assert_eq!(
+            BagQueryEngine::new(&huge_ids).query(&CubeCounts::default()),
+            BagQueryResult {
+                feasible_ids: vec![usize::MAX, 1],
+                id_sum: usize::MAX,
+            }
+        );
+    }
+
+    #[test]
+    fn test_display_cube_counts() {
+        assert_eq!(
+            CubeCounts::from([("red", 12), ("green", 13), ("blue", 0)]).to_string(),
+            "13 green, 12 red"
+        );
+    }
+
+    #[test]
+    fn test_parse_line_tolerates_formatting() {
+        let expected = Game {
+            id: 7,
+            reveals: vec![
+                Reveal::from([("red", 4), ("blue", 1)]),
+                Reveal::from([("green", 2)]),
+            ],
+        };
+
+        let tests = vec![
+            "Game 7: 4 red, 1 blue; 2 green",
+            "  Game   7 :4  red ,1 blue;2   green  ",
+            "Game 7:\t4 reds, 1 blue;\t2 greens;",
+            "Game 7: 4 Red, 1 BLUE,; 2 green; ;",
+            "game 7: 1 red, 3 red, 1 blue; 2 green",
+        ];
+
+        for input in tests {
+            assert_eq!(parse_line(input).as_ref(), Ok(&expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_line_color_names() {
+        let game = parse_line("Game 1: 2 dark  red, 1 brass, 3 golds").unwrap();
+
+        assert_eq!(
+            game.reveals,
+            vec![Reveal::from([("dark red", 2), ("brass", 1), ("gold", 3)])]
+        );
+    }
+
+    #[test]
+    fn test_parse_line_duplicate_policy() {
+        let input = "Game 1: 1 blue, 2 blues; 3 red";
+
+        assert_eq!(
+            parse_line_with(input, &ParseOptions::default())
+                .unwrap()
+                .reveals[0],
+            Reveal::from([("blue", 3)])
+        );
+        assert_eq!(
+            parse_line_with(
+                input,
+                &ParseOptions {
+                    duplicates: DuplicatePolicy::Reject,
+                }
+            ),
+            Err(ParseGameError::DuplicateColor("blue".to_string()))
+        );
+
+        let reject = ParseOptions {
+            duplicates: DuplicatePolicy::Reject,
+        };
+        assert_eq!(
+            parse_line_with("Game 1: 0 blue, 2 blue", &reject),
+            Err(ParseGameError::DuplicateColor("blue".to_string()))
+        );
+        assert_eq!(
+            parse_all_lines_with("Game 1: 1 red\nGame 2: 1 red, 1 red", &reject),
+            Err(LineError {
+                line: 2,
+                error: ParseGameError::DuplicateColor("red".to_string())
+            })
+        );
+        assert_eq!(
+            parse_line(format!("Game 1: {} red, 1 red", usize::MAX)),
+            Err(ParseGameError::TooManyCubes("red".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_line_errors() {
+        let tests = vec![
+            (
+                "3 blue, 4 red",
+                ParseGameError::MissingHeader("3 blue, 4 red".to_string()),
+            ),
+            (
+                "Round 1: 3 blue",
+                ParseGameError::MissingHeader("Round 1: 3 blue".to_string()),
+            ),
+            (
+                "Game one: 3 blue",
+                ParseGameError::InvalidGameId("one".to_string()),
+            ),
+            (
+                "Game 1: three blue",
+                ParseGameError::InvalidAmount("three".to_string()),
+            ),
+            (
+                "Game 1: 3 blue, -1 red",
+                ParseGameError::InvalidAmount("-1".to_string()),
+            ),
+            (
+                "Game 1: 3 blue, 4 ; 2 red",
+                ParseGameError::MissingColor("4".to_string()),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(parse_line(input), Err(expected));
+        }
+    }
+
+    #[test]
+    fn test_parse_all_lines_reports_line() {
+        assert_eq!(
+            parse_all_lines("Game 1: 1 red\n\nGame 2: red 1\n"),
+            Err(LineError {
+                line: 3,
+                error: ParseGameError::InvalidAmount("red".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_all_lines("Game 1: 1 red\n\n").unwrap(),
+            vec![Game {
+                id: 1,
+                reveals: vec![Reveal::from([("red", 1)])],
+            }]
+        );
+    }
 }
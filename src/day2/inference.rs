@@ -0,0 +1,236 @@
+use std::{cmp::Reverse, error::Error, fmt};
+
+use super::{get_game_amount, Color, CubeCounts, Game};
+
+/// The most candidate bags `infer_bags` will rank before giving up. Every
+/// candidate is kept in memory, so this is lower than the optimizer's limit.
+const MAX_CANDIDATES: u128 = 1_000_000;
+
+/// Returned when a game has too many candidate bags to rank: the number of
+/// bags multiplies with every colour.
+#[derive(Debug, PartialEq)]
+pub struct TooManyCandidates {
+    pub colors: usize,
+    pub bags: u128,
+}
+
+impl fmt::Display for TooManyCandidates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "{} colours give {} candidate bags, more than the {} allowed",
+            self.colors, self.bags, MAX_CANDIDATES
+        );
+    }
+}
+
+impl Error for TooManyCandidates {}
+
+/// How likely a bag is to have produced a game's reveals.
+#[derive(Debug, PartialEq)]
+pub struct BagEstimate {
+    pub bag: CubeCounts,
+    pub log_likelihood: f64,
+    /// The probability of this bag among all candidates, starting from a
+    /// uniform prior.
+    pub posterior: f64,
+}
+
+/// Ranks every bag that could have produced `game`, most plausible first.
+///
+/// Each reveal is taken to be a handful of cubes drawn without replacement
+/// from the bag and put back before the next one, so a reveal showing `k_c`
+/// cubes of each colour out of `K` has probability
+/// `prod(C(n_c, k_c)) / C(N, K)` for a bag holding `n_c` of each colour and
+/// `N` in total. Candidates hold only the colours the game showed, with each
+/// count between the most seen at once and `max_per_color`. Equally likely
+/// bags are ordered by size, so the first estimate is the smallest maximum
+/// likelihood bag. Past `MAX_CANDIDATES` bags this gives up with
+/// `TooManyCandidates`.
+pub fn infer_bags(
+    game: &Game,
+    max_per_color: usize,
+) -> Result<Vec<BagEstimate>, TooManyCandidates> {
+    let minimum_bag = get_game_amount(game);
+    let ranges: Vec<_> = minimum_bag
+        .iter()
+        .map(|(color, amount)| (color, amount..=max_per_color.max(amount)))
+        .collect();
+
+    let bags = ranges
+        .iter()
+        .map(|(_, range)| (range.end() - range.start()) as u128 + 1)
+        .fold(1u128, u128::saturating_mul);
+    if bags > MAX_CANDIDATES {
+        return Err(TooManyCandidates {
+            colors: ranges.len(),
+            bags,
+        });
+    }
+
+    let mut estimates = vec![];
+    let mut bag = CubeCounts::default();
+    collect_candidates(&ranges, &mut bag, &mut |bag| {
+        estimates.push(BagEstimate {
+            bag: bag.clone(),
+            log_likelihood: get_log_likelihood(game, bag),
+            posterior: 0.0,
+        });
+    });
+
+    let max_log_likelihood = estimates
+        .iter()
+        .map(|estimate| estimate.log_likelihood)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let total: f64 = estimates
+        .iter()
+        .map(|estimate| (estimate.log_likelihood - max_log_likelihood).exp())
+        .sum();
+    for estimate in &mut estimates {
+        estimate.posterior = (estimate.log_likelihood - max_log_likelihood).exp() / total;
+    }
+
+    // Rounded so bags that tie mathematically also tie after float errors.
+    estimates.sort_by_key(|estimate| {
+        (
+            Reverse((estimate.log_likelihood * 1e9).round() as i64),
+            total_cubes(&estimate.bag),
+        )
+    });
+
+    return Ok(estimates);
+}
+
+fn collect_candidates(
+    ranges: &[(Color, std::ops::RangeInclusive<usize>)],
+    bag: &mut CubeCounts,
+    visit: &mut impl FnMut(&CubeCounts),
+) {
+    let Some(((color, range), rest)) = ranges.split_first() else {
+        visit(bag);
+        return;
+    };
+
+    for amount in range.clone() {
+        bag.set(*color, amount);
+        collect_candidates(rest, bag, visit);
+    }
+}
+
+fn get_log_likelihood(game: &Game, bag: &CubeCounts) -> f64 {
+    let bag_total = total_cubes(bag);
+
+    return game
+        .reveals
+        .iter()
+        .map(|reveal| {
+            let ways: f64 = reveal
+                .iter()
+                .map(|(color, amount)| ln_choose(bag.get(color), amount))
+                .sum();
+
+            ways - ln_choose(bag_total, total_cubes(reveal))
+        })
+        .sum();
+}
+
+fn total_cubes(cubes: &CubeCounts) -> usize {
+    return cubes.iter().map(|(_, amount)| amount).sum();
+}
+
+/// The natural log of the binomial coefficient `C(n, k)`, or negative infinity
+/// when `k > n`.
+fn ln_choose(n: usize, k: usize) -> f64 {
+    if k > n {
+        return f64::NEG_INFINITY;
+    }
+
+    return (0..k.min(n - k))
+        .map(|i| ((n - i) as f64).ln() - ((i + 1) as f64).ln())
+        .sum();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day2::{parse_line, Color, CubeCounts};
+
+    use super::{infer_bags, ln_choose, TooManyCandidates};
+
+    #[test]
+    fn test_ln_choose() {
+        let tests = vec![(5, 0, 1.0), (5, 2, 10.0), (5, 5, 1.0), (10, 3, 120.0)];
+
+        for (n, k, expected) in tests {
+            assert!((ln_choose(n, k).exp() - expected).abs() < 1e-9);
+        }
+        assert_eq!(ln_choose(2, 3), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_infer_bags() {
+        let game = parse_line("Game 1: 1 red; 1 blue").unwrap();
+
+        let estimates = infer_bags(&game, 3).unwrap();
+
+        assert_eq!(estimates.len(), 9);
+
+        // The reveals have probability r/(r+b) and b/(r+b), best when r == b.
+        let total: f64 = (1..=3)
+            .flat_map(|r| (1..=3).map(move |b| (r * b) as f64 / ((r + b) * (r + b)) as f64))
+            .sum();
+
+        let best: Vec<_> = estimates.iter().take(3).map(|e| e.bag.clone()).collect();
+        assert_eq!(
+            best,
+            vec![
+                CubeCounts::from([("red", 1), ("blue", 1)]),
+                CubeCounts::from([("red", 2), ("blue", 2)]),
+                CubeCounts::from([("red", 3), ("blue", 3)]),
+            ]
+        );
+        assert!((estimates[0].posterior - 0.25 / total).abs() < 1e-9);
+        assert!((estimates.iter().map(|e| e.posterior).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(estimates
+            .windows(2)
+            .all(|pair| pair[0].posterior >= pair[1].posterior - 1e-12));
+    }
+
+    #[test]
+    fn test_infer_bags_respects_lower_bound() {
+        let game = parse_line("Game 1: 3 red, 1 green; 2 green").unwrap();
+
+        let estimates = infer_bags(&game, 4).unwrap();
+
+        assert_eq!(estimates.len(), 2 * 3);
+        assert!(estimates
+            .iter()
+            .all(|estimate| estimate.bag.get(Color::new("red")) >= 3));
+        assert!(estimates
+            .iter()
+            .all(|estimate| estimate.bag.get(Color::new("green")) >= 2));
+    }
+
+    #[test]
+    fn test_infer_bags_of_empty_game() {
+        let game = parse_line("Game 1:").unwrap();
+
+        let estimates = infer_bags(&game, 4).unwrap();
+
+        assert_eq!(estimates.len(), 1);
+        assert_eq!(estimates[0].bag, CubeCounts::default());
+        assert_eq!(estimates[0].posterior, 1.0);
+    }
+    #[test]
+    fn test_infer_bags_too_many_candidates() {
+        let game = parse_line("Game 1: 1 a, 1 b, 1 c, 1 d, 1 e, 1 f, 1 g, 1 h").unwrap();
+
+        assert_eq!(infer_bags(&game, 3).unwrap().len(), 3usize.pow(8));
+        assert_eq!(
+            infer_bags(&game, 20),
+            Err(TooManyCandidates {
+                colors: 8,
+                bags: 20u128.pow(8)
+            })
+        );
+    }
+}
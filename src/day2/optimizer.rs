@@ -0,0 +1,322 @@
+use std::{collections::BTreeMap, error::Error, fmt};
+
+use super::{Color, CubeCounts, Game};
+
+/// The most candidate bags `get_budget_frontier` will try before giving up.
+const MAX_BAGS_TRIED: u128 = 10_000_000;
+
+/// Returned when trying every candidate bag would take too long: the number
+/// of bags multiplies with every colour.
+#[derive(Debug, PartialEq)]
+pub struct SearchTooLarge {
+    pub colors: usize,
+    pub bags: u128,
+}
+
+impl fmt::Display for SearchTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "{} colours give {} candidate bags, more than the {} allowed",
+            self.colors, self.bags, MAX_BAGS_TRIED
+        );
+    }
+}
+
+impl Error for SearchTooLarge {}
+
+/// A bag together with the games it admits. Bags coming out of this module are
+/// always tight: every colour holds exactly the most any admitted game needs.
+#[derive(Debug, PartialEq)]
+pub struct BagChoice {
+    pub bag: CubeCounts,
+    pub game_ids: Vec<usize>,
+}
+
+impl BagChoice {
+    pub fn total_cubes(&self) -> usize {
+        return self.bag.iter().map(|(_, amount)| amount).sum();
+    }
+}
+
+/// The smallest bag admitting every game in `games`. For a fixed set of games
+/// the Pareto-minimal bags collapse to this single one: each colour needs at
+/// least the largest count any of them showed, and nothing above that.
+pub fn get_minimal_bag<'a>(games: impl IntoIterator<Item = &'a Game>) -> CubeCounts {
+    let mut bag = CubeCounts::default();
+
+    for game in games {
+        for (color, amount) in game.minimum_bag().iter() {
+            if bag.get(color) < amount {
+                bag.set(color, amount);
+            }
+        }
+    }
+
+    return bag;
+}
+
+/// The trade-off between bag size and admitted games: for every number of
+/// games, the bag with the fewest cubes admitting that many, keeping only the
+/// points where admitting more games really costs more cubes. Ordered from the
+/// smallest bag to the largest.
+///
+/// Only counts some game actually needs are tried for each colour, but every
+/// combination of them is, so the cost grows exponentially with the number of
+/// colours; past `MAX_BAGS_TRIED` bags this gives up with `SearchTooLarge`.
+pub fn get_budget_frontier(games: &[Game]) -> Result<Vec<BagChoice>, SearchTooLarge> {
+    let requirements: Vec<_> = games
+        .iter()
+        .map(|game| (game.id, game.minimum_bag()))
+        .collect();
+
+    let mut candidates: BTreeMap<Color, Vec<usize>> = BTreeMap::new();
+    for (_, bag) in &requirements {
+        for (color, amount) in bag.iter() {
+            candidates
+                .entry(color)
+                .or_insert_with(|| vec![0])
+                .push(amount);
+        }
+    }
+    for amounts in candidates.values_mut() {
+        amounts.sort_unstable();
+        amounts.dedup();
+    }
+    let candidates: Vec<_> = candidates.into_iter().collect();
+
+    let bags = candidates
+        .iter()
+        .map(|(_, amounts)| amounts.len() as u128)
+        .fold(1u128, u128::saturating_mul);
+    if bags > MAX_BAGS_TRIED {
+        return Err(SearchTooLarge {
+            colors: candidates.len(),
+            bags,
+        });
+    }
+
+    let mut cheapest_by_games: BTreeMap<usize, BagChoice> = BTreeMap::new();
+    let admitted: Vec<_> = requirements.iter().collect();
+    explore(&candidates, admitted, &mut |admitted| {
+        let choice = BagChoice {
+            bag: get_bag_of_requirements(admitted),
+            game_ids: admitted.iter().map(|(id, _)| *id).collect(),
+        };
+
+        match cheapest_by_games.get(&choice.game_ids.len()) {
+            Some(cheapest) if cheapest.total_cubes() <= choice.total_cubes() => {}
+            _ => {
+                cheapest_by_games.insert(choice.game_ids.len(), choice);
+            }
+        }
+    });
+
+    let mut frontier = vec![];
+    let mut cheapest_above = usize::MAX;
+    for (_, choice) in cheapest_by_games.into_iter().rev() {
+        if choice.total_cubes() < cheapest_above {
+            cheapest_above = choice.total_cubes();
+            frontier.push(choice);
+        }
+    }
+    frontier.reverse();
+
+    return Ok(frontier);
+}
+
+/// The bag admitting the most games without holding more than `budget` cubes
+/// in total, preferring fewer cubes among equally good bags.
+pub fn get_max_games_within_budget(
+    games: &[Game],
+    budget: usize,
+) -> Result<BagChoice, SearchTooLarge> {
+    return Ok(get_budget_frontier(games)?
+        .into_iter()
+        .take_while(|choice| choice.total_cubes() <= budget)
+        .last()
+        .unwrap_or(BagChoice {
+            bag: CubeCounts::default(),
+            game_ids: vec![],
+        }));
+}
+
+/// Tries every combination of candidate counts, one colour at a time, narrowing
+/// down the admitted games as it goes, and calls `visit` for each full bag.
+fn explore(
+    candidates: &[(Color, Vec<usize>)],
+    admitted: Vec<&(usize, CubeCounts)>,
+    visit: &mut impl FnMut(&[&(usize, CubeCounts)]),
+) {
+    let Some(((color, amounts), rest)) = candidates.split_first() else {
+        visit(&admitted);
+        return;
+    };
+
+    for amount in amounts {
+        let still_admitted: Vec<_> = admitted
+            .iter()
+            .filter(|(_, bag)| bag.get(*color) <= *amount)
+            .copied()
+            .collect();
+
+        explore(rest, still_admitted, visit);
+    }
+}
+
+fn get_bag_of_requirements(requirements: &[&(usize, CubeCounts)]) -> CubeCounts {
+    let mut bag = CubeCounts::default();
+
+    for (_, requirement) in requirements {
+        for (color, amount) in requirement.iter() {
+            if bag.get(color) < amount {
+                bag.set(color, amount);
+            }
+        }
+    }
+
+    return bag;
+}
+
+/// Parses a selection of game ids such as "1..50", "3,7,9" or "1..5,9", where
+/// ranges include both ends.
+pub fn parse_id_selection(selection: &str) -> Result<Vec<usize>, String> {
+    let mut ids = vec![];
+
+    for part in selection.split(',') {
+        match part.trim().split_once("..") {
+            Some((start, end)) => {
+                let start: usize = start.trim().parse().map_err(|_| part.to_string())?;
+                let end: usize = end.trim().parse().map_err(|_| part.to_string())?;
+                ids.extend(start..=end);
+            }
+            None => ids.push(part.trim().parse().map_err(|_| part.to_string())?),
+        }
+    }
+
+    return Ok(ids);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day2::{parse_line, tests::example_games, CubeCounts, Game};
+
+    use super::{
+        get_budget_frontier, get_max_games_within_budget, get_minimal_bag, parse_id_selection,
+        BagChoice, SearchTooLarge,
+    };
+
+    #[test]
+    fn test_get_minimal_bag() {
+        let games = example_games();
+
+        assert_eq!(
+            get_minimal_bag(games.iter().filter(|game| game.id <= 2)),
+            CubeCounts::from([("red", 4), ("green", 3), ("blue", 6)])
+        );
+        assert_eq!(
+            get_minimal_bag(&games),
+            CubeCounts::from([("red", 20), ("green", 13), ("blue", 15)])
+        );
+        assert_eq!(get_minimal_bag(&[]), CubeCounts::default());
+    }
+
+    #[test]
+    fn test_get_budget_frontier() {
+        let frontier = get_budget_frontier(&example_games()).unwrap();
+
+        assert_eq!(
+            frontier,
+            vec![
+                BagChoice {
+                    bag: CubeCounts::default(),
+                    game_ids: vec![],
+                },
+                BagChoice {
+                    bag: CubeCounts::from([("red", 1), ("green", 3), ("blue", 4)]),
+                    game_ids: vec![2],
+                },
+                BagChoice {
+                    bag: CubeCounts::from([("red", 6), ("green", 3), ("blue", 4)]),
+                    game_ids: vec![2, 5],
+                },
+                BagChoice {
+                    bag: CubeCounts::from([("red", 6), ("green", 3), ("blue", 6)]),
+                    game_ids: vec![1, 2, 5],
+                },
+                BagChoice {
+                    bag: CubeCounts::from([("red", 14), ("green", 3), ("blue", 15)]),
+                    game_ids: vec![1, 2, 4, 5],
+                },
+                BagChoice {
+                    bag: CubeCounts::from([("red", 20), ("green", 13), ("blue", 15)]),
+                    game_ids: vec![1, 2, 3, 4, 5],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_max_games_within_budget() {
+        let games = example_games();
+
+        let tests = vec![
+            (0, vec![]),
+            (7, vec![]),
+            (8, vec![2]),
+            (15, vec![1, 2, 5]),
+            (31, vec![1, 2, 5]),
+            (32, vec![1, 2, 4, 5]),
+            (1000, vec![1, 2, 3, 4, 5]),
+        ];
+
+        for (budget, expected) in tests {
+            let choice = get_max_games_within_budget(&games, budget).unwrap();
+            assert_eq!(choice.game_ids, expected, "budget {}", budget);
+            assert!(choice.total_cubes() <= budget);
+        }
+    }
+
+    #[test]
+    fn test_get_budget_frontier_too_large() {
+        // Eight colours with four candidate counts each, 0 included, is 4^8 bags.
+        let colors = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let games: Vec<Game> = (1..=3)
+            .map(|amount| {
+                let reveal: Vec<String> = colors
+                    .iter()
+                    .map(|color| format!("{} {}", amount, color))
+                    .collect();
+                parse_line(format!("Game {}: {}", amount, reveal.join(", "))).unwrap()
+            })
+            .collect();
+        assert_eq!(get_budget_frontier(&games).unwrap().len(), 4);
+
+        let colors: Vec<String> = (0..12).map(|index| format!("colour{}", index)).collect();
+        let games: Vec<Game> = (1..=4)
+            .map(|amount| {
+                let reveal: Vec<String> = colors
+                    .iter()
+                    .map(|color| format!("{} {}", amount, color))
+                    .collect();
+                parse_line(format!("Game {}: {}", amount, reveal.join(", "))).unwrap()
+            })
+            .collect();
+        assert_eq!(
+            get_budget_frontier(&games),
+            Err(SearchTooLarge {
+                colors: 12,
+                bags: 5u128.pow(12)
+            })
+        );
+        assert!(get_max_games_within_budget(&games, 10).is_err());
+    }
+
+    #[test]
+    fn test_parse_id_selection() {
+        assert_eq!(parse_id_selection("1..3"), Ok(vec![1, 2, 3]));
+        assert_eq!(parse_id_selection("3, 7,9"), Ok(vec![3, 7, 9]));
+        assert_eq!(parse_id_selection("1..2,9"), Ok(vec![1, 2, 9]));
+        assert_eq!(parse_id_selection("1..x"), Err("1..x".to_string()));
+    }
+}
@@ -0,0 +1,547 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use super::{normalize_color_name, Color, CubeCounts, Game};
+
+/// A condition on cube counts, such as `red <= 12 and (green + blue) > 10`,
+/// optionally followed by where to check it:
+///
+/// - `in any reveal`: at least one reveal satisfies it,
+/// - `in every reveal` (or `in all reveals`): all of them do,
+/// - `in game`, the default: the game's minimum bag does.
+///
+/// Any word that isn't a keyword names a colour and stands for its count.
+/// Names are read like the game parser reads them, so `Blues` is `blue`;
+/// names of several words, or ones clashing with a keyword, go in double
+/// quotes, as in `"dark red" > 2`.
+#[derive(Debug, PartialEq)]
+pub struct Query {
+    condition: Expr,
+    scope: Scope,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+    Game,
+    AnyReveal,
+    EveryReveal,
+}
+
+#[derive(Debug, PartialEq)]
+enum Expr {
+    Number(i64),
+    Count(Color),
+    Binary(Box<Expr>, Operator, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Number,
+    Condition,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    UnexpectedCharacter(char),
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    /// A number used as a condition or a condition used as a number.
+    TypeMismatch(String),
+    /// Arithmetic went past what an `i64` holds while evaluating.
+    Overflow,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            QueryError::UnexpectedCharacter(ch) => write!(f, "unexpected character '{}'", ch),
+            QueryError::UnexpectedToken(token) => write!(f, "unexpected \"{}\"", token),
+            QueryError::UnexpectedEnd => write!(f, "unexpected end of query"),
+            QueryError::TypeMismatch(message) => write!(f, "{}", message),
+            QueryError::Overflow => write!(f, "arithmetic overflow"),
+        };
+    }
+}
+
+impl Error for QueryError {}
+
+impl Query {
+    pub fn matches(&self, game: &Game) -> Result<bool, QueryError> {
+        let holds = |cubes: &CubeCounts| self.condition.evaluate(cubes).map(|value| value != 0);
+
+        return match self.scope {
+            Scope::Game => holds(&game.minimum_bag()),
+            Scope::AnyReveal => {
+                for reveal in &game.reveals {
+                    if holds(reveal)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Scope::EveryReveal => {
+                for reveal in &game.reveals {
+                    if !holds(reveal)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        };
+    }
+
+    /// The ids of the games in `games` matching this query.
+    pub fn find_matching(&self, games: &[Game]) -> Result<Vec<usize>, QueryError> {
+        let mut ids = vec![];
+        for game in games {
+            if self.matches(game)? {
+                ids.push(game.id);
+            }
+        }
+
+        return Ok(ids);
+    }
+}
+
+impl Expr {
+    /// Evaluates against `cubes`, with conditions coming out as 1 or 0.
+    fn evaluate(&self, cubes: &CubeCounts) -> Result<i64, QueryError> {
+        return Ok(match self {
+            Expr::Number(number) => *number,
+            Expr::Count(color) => {
+                i64::try_from(cubes.get(*color)).map_err(|_| QueryError::Overflow)?
+            }
+            Expr::Not(inner) => (inner.evaluate(cubes)? == 0) as i64,
+            Expr::Binary(left, Operator::And, right) => {
+                (left.evaluate(cubes)? != 0 && right.evaluate(cubes)? != 0) as i64
+            }
+            Expr::Binary(left, Operator::Or, right) => {
+                (left.evaluate(cubes)? != 0 || right.evaluate(cubes)? != 0) as i64
+            }
+            Expr::Binary(left, operator, right) => {
+                let (left, right) = (left.evaluate(cubes)?, right.evaluate(cubes)?);
+
+                match operator {
+                    Operator::Add => left.checked_add(right).ok_or(QueryError::Overflow)?,
+                    Operator::Subtract => left.checked_sub(right).ok_or(QueryError::Overflow)?,
+                    Operator::Multiply => left.checked_mul(right).ok_or(QueryError::Overflow)?,
+                    Operator::Less => (left < right) as i64,
+                    Operator::LessOrEqual => (left <= right) as i64,
+                    Operator::Greater => (left > right) as i64,
+                    Operator::GreaterOrEqual => (left >= right) as i64,
+                    Operator::Equal => (left == right) as i64,
+                    Operator::NotEqual => (left != right) as i64,
+                    Operator::And | Operator::Or => unreachable!(),
+                }
+            }
+        });
+    }
+
+    fn get_type(&self) -> Result<Type, QueryError> {
+        return match self {
+            Expr::Number(_) | Expr::Count(_) => Ok(Type::Number),
+            Expr::Not(inner) => {
+                expect_type(inner, Type::Condition, "not")?;
+                Ok(Type::Condition)
+            }
+            Expr::Binary(left, operator, right) => {
+                let (operands, result) = match operator {
+                    Operator::Add | Operator::Subtract | Operator::Multiply => {
+                        (Type::Number, Type::Number)
+                    }
+                    Operator::And | Operator::Or => (Type::Condition, Type::Condition),
+                    _ => (Type::Number, Type::Condition),
+                };
+
+                expect_type(left, operands, operator.symbol())?;
+                expect_type(right, operands, operator.symbol())?;
+                Ok(result)
+            }
+        };
+    }
+}
+
+fn expect_type(expr: &Expr, expected: Type, context: &str) -> Result<(), QueryError> {
+    if expr.get_type()? != expected {
+        return Err(QueryError::TypeMismatch(format!(
+            "\"{}\" expects {}",
+            context,
+            match expected {
+                Type::Number => "numbers",
+                Type::Condition => "conditions",
+            }
+        )));
+    }
+
+    return Ok(());
+}
+
+impl Operator {
+    fn symbol(&self) -> &'static str {
+        return match self {
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::And => "and",
+            Operator::Or => "or",
+        };
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+
+        let condition = parser.parse_or()?;
+        let scope = parser.parse_scope()?;
+        if let Some(token) = parser.peek() {
+            return Err(QueryError::UnexpectedToken(token.to_string()));
+        }
+
+        if condition.get_type()? != Type::Condition {
+            return Err(QueryError::TypeMismatch(
+                "a query must be a condition, not a number".to_string(),
+            ));
+        }
+
+        return Ok(Query { condition, scope });
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<String>, QueryError> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+        } else if ch.is_ascii_digit() || ch.is_alphabetic() {
+            let mut token = String::new();
+            while let Some(&ch) = chars.peek() {
+                if !(ch.is_alphanumeric() || ch == '_') {
+                    break;
+                }
+                token.push(ch);
+                chars.next();
+            }
+            tokens.push(token);
+        } else if "<>=!".contains(ch) {
+            chars.next();
+            let mut token = ch.to_string();
+            if chars.peek() == Some(&'=') {
+                chars.next();
+                token.push('=');
+            }
+            tokens.push(token);
+        } else if "+-*()".contains(ch) {
+            chars.next();
+            tokens.push(ch.to_string());
+        } else if ch == '"' {
+            // Kept with its quotes so it can't be mistaken for a keyword.
+            chars.next();
+            let mut token = "\"".to_string();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(ch) => token.push(ch),
+                    None => return Err(QueryError::UnexpectedEnd),
+                }
+            }
+            token.push('"');
+            tokens.push(token);
+        } else {
+            return Err(QueryError::UnexpectedCharacter(ch));
+        }
+    }
+
+    return Ok(tokens);
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        return self.tokens.get(self.position).map(|token| token.as_str());
+    }
+
+    fn next(&mut self) -> Result<&str, QueryError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .ok_or(QueryError::UnexpectedEnd)?;
+        self.position += 1;
+
+        return Ok(token.as_str());
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            return true;
+        }
+
+        return false;
+    }
+
+    fn parse_scope(&mut self) -> Result<Scope, QueryError> {
+        if !self.eat("in") {
+            return Ok(Scope::Game);
+        }
+
+        let scope = match self.next()? {
+            "game" => return Ok(Scope::Game),
+            "any" => Scope::AnyReveal,
+            "every" | "all" => Scope::EveryReveal,
+            token => return Err(QueryError::UnexpectedToken(token.to_string())),
+        };
+
+        return match self.next()? {
+            "reveal" | "reveals" => Ok(scope),
+            token => Err(QueryError::UnexpectedToken(token.to_string())),
+        };
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.parse_and()?;
+        while self.eat("or") {
+            expr = Expr::Binary(Box::new(expr), Operator::Or, Box::new(self.parse_and()?));
+        }
+
+        return Ok(expr);
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.parse_not()?;
+        while self.eat("and") {
+            expr = Expr::Binary(Box::new(expr), Operator::And, Box::new(self.parse_not()?));
+        }
+
+        return Ok(expr);
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, QueryError> {
+        if self.eat("not") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+
+        return self.parse_comparison();
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, QueryError> {
+        let left = self.parse_sum()?;
+
+        let operator = match self.peek() {
+            Some("<") => Operator::Less,
+            Some("<=") => Operator::LessOrEqual,
+            Some(">") => Operator::Greater,
+            Some(">=") => Operator::GreaterOrEqual,
+            Some("=") | Some("==") => Operator::Equal,
+            Some("!=") => Operator::NotEqual,
+            _ => return Ok(left),
+        };
+        self.position += 1;
+
+        return Ok(Expr::Binary(
+            Box::new(left),
+            operator,
+            Box::new(self.parse_sum()?),
+        ));
+    }
+
+    fn parse_sum(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.parse_product()?;
+
+        loop {
+            let operator = match self.peek() {
+                Some("+") => Operator::Add,
+                Some("-") => Operator::Subtract,
+                _ => return Ok(expr),
+            };
+            self.position += 1;
+
+            expr = Expr::Binary(Box::new(expr), operator, Box::new(self.parse_product()?));
+        }
+    }
+
+    fn parse_product(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.parse_primary()?;
+        while self.eat("*") {
+            expr = Expr::Binary(
+                Box::new(expr),
+                Operator::Multiply,
+                Box::new(self.parse_primary()?),
+            );
+        }
+
+        return Ok(expr);
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, QueryError> {
+        let token = self.next()?.to_string();
+
+        if token == "(" {
+            let expr = self.parse_or()?;
+            if !self.eat(")") {
+                return match self.peek() {
+                    Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+                    None => Err(QueryError::UnexpectedEnd),
+                };
+            }
+
+            return Ok(expr);
+        }
+
+        if let Ok(number) = token.parse() {
+            return Ok(Expr::Number(number));
+        }
+
+        if let Some(name) = token
+            .strip_prefix('"')
+            .and_then(|name| name.strip_suffix('"'))
+        {
+            let name = normalize_color_name(name);
+            if name.is_empty() {
+                return Err(QueryError::UnexpectedToken(token));
+            }
+
+            return Ok(Expr::Count(Color::new(&name)));
+        }
+
+        let is_keyword = ["and", "or", "not", "in"].contains(&token.as_str());
+        if token.chars().next().is_some_and(char::is_alphabetic) && !is_keyword {
+            return Ok(Expr::Count(Color::new(&normalize_color_name(&token))));
+        }
+
+        return Err(QueryError::UnexpectedToken(token));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day2::{parse_all_lines, tests::example_games};
+
+    use super::{Query, QueryError};
+
+    #[test]
+    fn test_find_matching() {
+        let games = example_games();
+
+        let tests = vec![
+            ("red <= 12 and green <= 13 and blue <= 14", vec![1, 2, 5]),
+            ("red <= 12 and (green + blue) > 10 in any reveal", vec![3]),
+            ("red <= 12 and (green + blue) > 7 in game", vec![1]),
+            ("green >= 1 in every reveal", vec![2, 3, 4, 5]),
+            ("green >= 1 in all reveals", vec![2, 3, 4, 5]),
+            ("not (red > 4 or blue > 4)", vec![2]),
+            ("red * green * blue = 48", vec![1]),
+            ("red - blue > 10 in any reveal", vec![3]),
+            ("RED != 20 and yellow == 0", vec![1, 2, 4, 5]),
+            ("Blues > 14", vec![4]),
+            ("\"Green\" >= 13 and \"dark red\" = 0", vec![3]),
+            (
+                "red > 1 and blue > 5 or green = 1 in any reveal",
+                vec![2, 3, 4],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let query: Query = input.parse().unwrap();
+            assert_eq!(query.find_matching(&games), Ok(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_multi_word_colours() {
+        let games = parse_all_lines("Game 7: 2 dark red, 1 red; 3 Dark Reds").unwrap();
+
+        let query: Query = "\"dark red\" = 3 and red = 0 in any reveal"
+            .parse()
+            .unwrap();
+        assert_eq!(query.find_matching(&games), Ok(vec![7]));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let games = example_games();
+
+        let query: Query = "red * 9999999999 * 9999999999 > 1".parse().unwrap();
+        assert_eq!(query.find_matching(&games), Err(QueryError::Overflow));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let tests = vec![
+            ("red <= ", QueryError::UnexpectedEnd),
+            ("red <= 12 )", QueryError::UnexpectedToken(")".to_string())),
+            ("(red <= 12", QueryError::UnexpectedEnd),
+            (
+                "red < 1 in some reveal",
+                QueryError::UnexpectedToken("some".to_string()),
+            ),
+            (
+                "red < 1 in any game",
+                QueryError::UnexpectedToken("game".to_string()),
+            ),
+            ("red & blue", QueryError::UnexpectedCharacter('&')),
+            ("\"dark red > 1", QueryError::UnexpectedEnd),
+            (
+                "\" \" > 1",
+                QueryError::UnexpectedToken("\" \"".to_string()),
+            ),
+            (
+                "red blue < 1",
+                QueryError::UnexpectedToken("blue".to_string()),
+            ),
+            (
+                "red + blue",
+                QueryError::TypeMismatch("a query must be a condition, not a number".to_string()),
+            ),
+            (
+                "red and blue > 1",
+                QueryError::TypeMismatch("\"and\" expects conditions".to_string()),
+            ),
+            (
+                "(red > 1) + 2 > 1",
+                QueryError::TypeMismatch("\"+\" expects numbers".to_string()),
+            ),
+            (
+                "not red",
+                QueryError::TypeMismatch("\"not\" expects conditions".to_string()),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(input.parse::<Query>(), Err(expected), "{}", input);
+        }
+    }
+}
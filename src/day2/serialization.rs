@@ -0,0 +1,382 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt,
+};
+
+use super::{normalize_color_name, Color, Game, ParseOptions, Reveal};
+
+pub fn to_json(games: &[Game]) -> String {
+    return serde_json::to_string_pretty(games).unwrap();
+}
+
+pub fn from_json(contents: &str) -> Result<Vec<Game>, serde_json::Error> {
+    return serde_json::from_str(contents);
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CsvError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "line {}: {}", self.line, self.message);
+    }
+}
+
+impl Error for CsvError {}
+
+/// Writes one row per reveal: the game id, the (1-based) reveal number and a
+/// column per colour seen anywhere in `games`. Colour names holding commas or
+/// quotes are quoted. Games without reveals have no rows and don't survive a
+/// round trip.
+pub fn to_csv(games: &[Game]) -> String {
+    let colors: BTreeSet<Color> = games
+        .iter()
+        .flat_map(|game| &game.reveals)
+        .flat_map(|reveal| reveal.iter().map(|(color, _)| color))
+        .collect();
+
+    let mut header = vec!["game".to_string(), "reveal".to_string()];
+    header.extend(colors.iter().map(|color| quote_csv_field(color.0)));
+
+    let mut csv = header.join(",");
+    csv.push('\n');
+
+    for game in games {
+        for (index, reveal) in game.reveals.iter().enumerate() {
+            let mut row = vec![game.id.to_string(), (index + 1).to_string()];
+            row.extend(colors.iter().map(|color| reveal.get(*color).to_string()));
+
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+    }
+
+    return csv;
+}
+
+fn quote_csv_field(field: &str) -> String {
+    if field.contains([',', '"']) {
+        return format!("\"{}\"", field.replace('"', "\"\""));
+    }
+
+    return field.to_string();
+}
+
+/// Splits a CSV row into trimmed fields, undoing `quote_csv_field`. Quoted
+/// fields can't span lines, which colour names never need.
+fn split_csv_row(row: &str, line: usize) -> Result<Vec<String>, CsvError> {
+    let mut fields = vec![];
+    let mut chars = row.chars().peekable();
+
+    loop {
+        while chars.next_if(|ch| *ch == ' ' || *ch == '\t').is_some() {}
+
+        let mut field = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') if chars.next_if_eq(&'"').is_some() => field.push('"'),
+                    Some('"') => break,
+                    Some(ch) => field.push(ch),
+                    None => {
+                        return Err(CsvError {
+                            line,
+                            message: "unterminated quoted field".to_string(),
+                        })
+                    }
+                }
+            }
+            while chars.next_if(|ch| *ch == ' ' || *ch == '\t').is_some() {}
+            if chars.peek().is_some_and(|ch| *ch != ',') {
+                return Err(CsvError {
+                    line,
+                    message: "unexpected text after a quoted field".to_string(),
+                });
+            }
+        } else {
+            while let Some(ch) = chars.next_if(|ch| *ch != ',') {
+                field.push(ch);
+            }
+            field = field.trim().to_string();
+        }
+        fields.push(field);
+
+        if chars.next().is_none() {
+            return Ok(fields);
+        }
+    }
+}
+
+/// Reads the format written by `to_csv`. Rows of the same game are gathered
+/// in the order they appear, and games keep the order of their first row.
+/// Each game's reveal numbers must count up from 1 in that order.
+pub fn from_csv(contents: &str) -> Result<Vec<Game>, CsvError> {
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let (_, header) = lines.next().ok_or(CsvError {
+        line: 1,
+        message: "missing header".to_string(),
+    })?;
+
+    let header_fields = split_csv_row(header, 1)?;
+    let colors: Vec<_> = match header_fields.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["game", "reveal", ref colors @ ..] => colors
+            .iter()
+            .map(|name| Color::new(&normalize_color_name(name)))
+            .collect(),
+        _ => {
+            return Err(CsvError {
+                line: 1,
+                message: format!("expected a \"game,reveal,...\" header, got \"{}\"", header),
+            })
+        }
+    };
+
+    let mut games: Vec<Game> = vec![];
+    let mut index_of_game: HashMap<usize, usize> = HashMap::new();
+
+    for (line, row) in lines {
+        let fields = split_csv_row(row, line)?
+            .iter()
+            .map(|field| field.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| CsvError {
+                line,
+                message: error.to_string(),
+            })?;
+
+        if fields.len() != colors.len() + 2 {
+            return Err(CsvError {
+                line,
+                message: format!("expected {} fields, got {}", colors.len() + 2, fields.len()),
+            });
+        }
+
+        let (id, reveal_number) = (fields[0], fields[1]);
+        let index = *index_of_game.entry(id).or_insert_with(|| {
+            games.push(Game {
+                id,
+                reveals: vec![],
+            });
+            games.len() - 1
+        });
+        let game = &mut games[index];
+
+        if reveal_number != game.reveals.len() + 1 {
+            return Err(CsvError {
+                line,
+                message: format!(
+                    "expected reveal {} of game {}, got {}",
+                    game.reveals.len() + 1,
+                    id,
+                    reveal_number
+                ),
+            });
+        }
+
+        let mut reveal = Reveal::default();
+        for (color, amount) in colors.iter().zip(&fields[2..]) {
+            let total = reveal.get(*color).checked_add(*amount).ok_or(CsvError {
+                line,
+                message: format!("too many \"{}\" cubes", color.0),
+            })?;
+            reveal.set(*color, total);
+        }
+        game.reveals.push(reveal);
+    }
+
+    return Ok(games);
+}
+
+/// Reads games from `path`, picking the format from its extension: ".json",
+/// ".csv", or the puzzle text format parsed with `options` for anything else.
+pub fn read_games_from(path: &str, options: &ParseOptions) -> Result<Vec<Game>, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)?;
+
+    if path.ends_with(".json") {
+        return Ok(from_json(&contents)?);
+    }
+    if path.ends_with(".csv") {
+        return Ok(from_csv(&contents)?);
+    }
+
+    return Ok(super::parse_all_lines_with(&contents, options)?);
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::day2::{parse_all_lines, Game, Reveal};
+
+    use super::{from_csv, from_json, to_csv, to_json, CsvError};
+
+    fn games_with_yellow() -> Vec<Game> {
+        return parse_all_lines(indoc! {"
+            Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+            Game 2: 1 blue, 2 yellow
+        "})
+        .unwrap();
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let games = games_with_yellow();
+
+        let json = to_json(&games);
+        assert_eq!(from_json(&json).unwrap(), games);
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!([
+                {
+                    "id": 1,
+                    "reveals": [
+                        { "blue": 3, "red": 4 },
+                        { "blue": 6, "green": 2, "red": 1 },
+                        { "green": 2 }
+                    ]
+                },
+                {
+                    "id": 2,
+                    "reveals": [{ "blue": 1, "yellow": 2 }]
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_from_json_drops_zero_counts() {
+        let games = from_json(r#"[{ "id": 4, "reveals": [{ "red": 0, "blue": 2 }, {}] }]"#);
+
+        assert_eq!(
+            games.unwrap(),
+            vec![Game {
+                id: 4,
+                reveals: vec![Reveal::from([("blue", 2)]), Reveal::default()],
+            }]
+        );
+        assert!(from_json(r#"[{ "id": 4, "reveals": [{ "red": -1 }] }]"#).is_err());
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let games = games_with_yellow();
+
+        let csv = to_csv(&games);
+        assert_eq!(
+            csv,
+            indoc! {"
+                game,reveal,blue,green,red,yellow
+                1,1,3,0,4,0
+                1,2,6,2,1,0
+                1,3,0,2,0,0
+                2,1,1,0,0,2
+            "}
+        );
+        assert_eq!(from_csv(&csv).unwrap(), games);
+    }
+
+    #[test]
+    fn test_from_csv_groups_rows_by_game() {
+        let csv = indoc! {"
+            game, reveal, red
+            2, 1, 5
+
+            1, 1, 3
+            2, 2, 1
+        "};
+
+        assert_eq!(
+            from_csv(csv).unwrap(),
+            vec![
+                Game {
+                    id: 2,
+                    reveals: vec![Reveal::from([("red", 5)]), Reveal::from([("red", 1)])],
+                },
+                Game {
+                    id: 1,
+                    reveals: vec![Reveal::from([("red", 3)])],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_csv_errors() {
+        assert_eq!(
+            from_csv(""),
+            Err(CsvError {
+                line: 1,
+                message: "missing header".to_string(),
+            })
+        );
+        assert_eq!(
+            from_csv("id,red\n1,2\n"),
+            Err(CsvError {
+                line: 1,
+                message: "expected a \"game,reveal,...\" header, got \"id,red\"".to_string(),
+            })
+        );
+        assert_eq!(
+            from_csv("game,reveal,red\n1,1\n"),
+            Err(CsvError {
+                line: 2,
+                message: "expected 3 fields, got 2".to_string(),
+            })
+        );
+        assert_eq!(from_csv("game,reveal,red\n1,1,x\n").unwrap_err().line, 2);
+        assert_eq!(
+            from_csv("game,reveal,red\n1,1,2\n1,3,2\n"),
+            Err(CsvError {
+                line: 3,
+                message: "expected reveal 2 of game 1, got 3".to_string(),
+            })
+        );
+        assert_eq!(
+            from_csv("game,reveal,\"red\n1,1,2\n"),
+            Err(CsvError {
+                line: 1,
+                message: "unterminated quoted field".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_csv_quotes_colour_names() {
+        let games = from_json(r#"[{ "id": 1, "reveals": [{ "dark, red": 2, "say \"hi\"": 1 }] }]"#)
+            .unwrap();
+
+        let csv = to_csv(&games);
+        assert_eq!(
+            csv,
+            indoc! {r#"
+                game,reveal,"dark, red","say ""hi"""
+                1,1,2,1
+            "#}
+        );
+        assert_eq!(from_csv(&csv).unwrap(), games);
+    }
+
+    #[test]
+    fn test_imported_colours_are_normalised() {
+        let games =
+            from_json(r#"[{ "id": 1, "reveals": [{ "Red": 2, "reds": 1, " Dark  Blue ": 3 }] }]"#);
+        assert_eq!(
+            games.unwrap()[0].reveals[0],
+            Reveal::from([("red", 3), ("dark blue", 3)])
+        );
+        assert!(from_json(r#"[{ "id": 1, "reveals": [{ " ": 2 }] }]"#).is_err());
+
+        let games = from_csv("game,reveal,Red,REDS\n1,1,2,1\n").unwrap();
+        assert_eq!(games[0].reveals[0], Reveal::from([("red", 3)]));
+    }
+}
@@ -0,0 +1,247 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+
+use super::{get_game_amount, Color, CubeCounts, Game};
+
+/// A summary of the data behind the puzzle answers, meant to be printed as a
+/// table or serialized to JSON.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct GameStats {
+    pub games: usize,
+    pub reveals_per_game: Summary,
+    /// The most cubes shown at once in each game, saturating at `usize::MAX`.
+    pub largest_reveal_per_game: Summary,
+    pub colors: Vec<ColorStats>,
+    pub bag: CubeCounts,
+    /// How many games each colour makes infeasible for `bag`, most often first.
+    pub constraining_colors: Vec<(Color, usize)>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ColorStats {
+    pub color: Color,
+    /// How many reveals showed this colour at all.
+    pub reveals: usize,
+    pub counts: Summary,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Summary {
+    pub min: usize,
+    /// The middle value; for an even count, the upper of the two middle ones
+    /// so it stays a whole cube count.
+    pub median: usize,
+    pub max: usize,
+    pub mean: f64,
+}
+
+impl Summary {
+    /// Summarises `values`, which are sorted in place. No values summarise to
+    /// all zeros.
+    fn of(values: &mut [usize]) -> Self {
+        if values.is_empty() {
+            return Summary {
+                min: 0,
+                median: 0,
+                max: 0,
+                mean: 0.0,
+            };
+        }
+
+        values.sort_unstable();
+
+        return Summary {
+            min: values[0],
+            median: values[values.len() / 2],
+            max: values[values.len() - 1],
+            // Summed wider so that huge counts can't overflow.
+            mean: values.iter().map(|&value| value as u128).sum::<u128>() as f64
+                / values.len() as f64,
+        };
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "min {}, median {}, max {}, mean {:.2}",
+            self.min, self.median, self.max, self.mean
+        );
+    }
+}
+
+pub fn get_stats(games: &[Game], bag: &CubeCounts) -> GameStats {
+    let mut reveals_per_game: Vec<_> = games.iter().map(|game| game.reveals.len()).collect();
+    let mut largest_reveal_per_game: Vec<_> = games
+        .iter()
+        .map(|game| {
+            game.reveals
+                .iter()
+                .map(|reveal| {
+                    reveal
+                        .iter()
+                        .map(|(_, amount)| amount)
+                        .fold(0, usize::saturating_add)
+                })
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut counts_by_color: BTreeMap<Color, Vec<usize>> = BTreeMap::new();
+    for reveal in games.iter().flat_map(|game| &game.reveals) {
+        for (color, amount) in reveal.iter() {
+            counts_by_color.entry(color).or_default().push(amount);
+        }
+    }
+
+    let colors = counts_by_color
+        .into_iter()
+        .map(|(color, mut counts)| ColorStats {
+            color,
+            reveals: counts.len(),
+            counts: Summary::of(&mut counts),
+        })
+        .collect();
+
+    let mut constraining: BTreeMap<Color, usize> = BTreeMap::new();
+    for game in games {
+        for (color, amount) in get_game_amount(game).iter() {
+            if amount > bag.get(color) {
+                *constraining.entry(color).or_default() += 1;
+            }
+        }
+    }
+
+    let mut constraining_colors: Vec<_> = constraining.into_iter().collect();
+    constraining_colors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    return GameStats {
+        games: games.len(),
+        reveals_per_game: Summary::of(&mut reveals_per_game),
+        largest_reveal_per_game: Summary::of(&mut largest_reveal_per_game),
+        colors,
+        bag: bag.clone(),
+        constraining_colors,
+    };
+}
+
+impl fmt::Display for GameStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "games: {}", self.games)?;
+        writeln!(f, "reveals per game: {}", self.reveals_per_game)?;
+        writeln!(
+            f,
+            "largest reveal per game: {}",
+            self.largest_reveal_per_game
+        )?;
+        writeln!(f)?;
+
+        writeln!(
+            f,
+            "{:<10} {:>7} {:>5} {:>7} {:>5} {:>7}",
+            "colour", "reveals", "min", "median", "max", "mean"
+        )?;
+        for stats in &self.colors {
+            writeln!(
+                f,
+                "{:<10} {:>7} {:>5} {:>7} {:>5} {:>7.2}",
+                stats.color.0,
+                stats.reveals,
+                stats.counts.min,
+                stats.counts.median,
+                stats.counts.max,
+                stats.counts.mean
+            )?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "games made infeasible by ({}):", self.bag)?;
+        for (color, games) in &self.constraining_colors {
+            writeln!(f, "{:<10} {:>7}", color.0, games)?;
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day2::{parse_line, tests::example_games, Color, CubeCounts};
+
+    use super::{get_stats, Summary};
+
+    #[test]
+    fn test_summary_of() {
+        assert_eq!(
+            // The upper middle value of an even count.
+            Summary::of(&mut [4, 1, 3, 2]),
+            Summary {
+                min: 1,
+                median: 3,
+                max: 4,
+                mean: 2.5,
+            }
+        );
+        assert_eq!(
+            Summary::of(&mut []),
+            Summary {
+                min: 0,
+                median: 0,
+                max: 0,
+                mean: 0.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_stats() {
+        let games = example_games();
+        let bag = CubeCounts::from([("red", 12), ("green", 13), ("blue", 14)]);
+
+        let stats = get_stats(&games, &bag);
+
+        assert_eq!(stats.games, 5);
+        assert_eq!(
+            stats.reveals_per_game,
+            Summary {
+                min: 2,
+                median: 3,
+                max: 3,
+                mean: 2.8,
+            }
+        );
+        assert_eq!(stats.largest_reveal_per_game.max, 34);
+        assert_eq!(stats.largest_reveal_per_game.min, 8);
+
+        let red = stats
+            .colors
+            .iter()
+            .find(|stats| stats.color == Color::new("red"))
+            .unwrap();
+        assert_eq!(red.reveals, 11);
+        assert_eq!(red.counts.min, 1);
+        assert_eq!(red.counts.max, 20);
+
+        assert_eq!(
+            stats.constraining_colors,
+            vec![(Color::new("red"), 2), (Color::new("blue"), 1)]
+        );
+    }
+    #[test]
+    fn test_get_stats_with_huge_counts() {
+        let games = [parse_line(format!("Game 1: {} red, 1 blue", usize::MAX)).unwrap()];
+
+        let stats = get_stats(&games, &CubeCounts::default());
+
+        assert_eq!(stats.largest_reveal_per_game.max, usize::MAX);
+        let red = &stats.colors[1];
+        assert_eq!(red.counts.mean, usize::MAX as f64);
+        assert_eq!(
+            Summary::of(&mut [usize::MAX, usize::MAX]).mean,
+            usize::MAX as f64
+        );
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    io::{self, Read},
+    thread,
+    time::Duration,
+};
+
+use super::{parse_line_with, CubeCounts, Game, LineError, ParseOptions};
+
+/// The id of the game on a line, or why it couldn't be parsed.
+pub type LineResult = Result<usize, LineError>;
+
+/// The sums saturate at `usize::MAX` rather than overflowing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunningTotals {
+    pub games: usize,
+    pub feasible_id_sum: usize,
+    pub power_sum: usize,
+}
+
+/// Keeps the puzzle answers up to date while `Game N: ...` lines arrive in
+/// arbitrary pieces. A partial trailing line is held back until its newline
+/// shows up, so every line is parsed exactly once.
+pub struct GameLogEvaluator {
+    bag: CubeCounts,
+    options: ParseOptions,
+    totals: RunningTotals,
+    pending: Vec<u8>,
+    /// How much of `pending` is known to hold no newline.
+    scanned: usize,
+    line: usize,
+}
+
+impl GameLogEvaluator {
+    pub fn new(bag: CubeCounts, options: ParseOptions) -> Self {
+        GameLogEvaluator {
+            bag,
+            options,
+            totals: RunningTotals::default(),
+            pending: vec![],
+            scanned: 0,
+            line: 0,
+        }
+    }
+
+    pub fn totals(&self) -> &RunningTotals {
+        return &self.totals;
+    }
+
+    /// Takes in newly arrived bytes, calling `on_result` with the updated
+    /// totals and the id of every game they complete, or with the error of
+    /// each line that didn't parse.
+    pub fn feed(&mut self, bytes: &[u8], mut on_result: impl FnMut(&RunningTotals, LineResult)) {
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.extend_from_slice(bytes);
+
+        // Only the new bytes can hold a newline, and complete lines are
+        // dropped in one go, so a long partial line isn't rescanned.
+        let mut start = 0;
+        let mut scan_from = self.scanned;
+        while let Some(offset) = pending[scan_from..].iter().position(|byte| *byte == b'\n') {
+            let end = scan_from + offset;
+
+            if let Some(result) = self.evaluate_line(&pending[start..end]) {
+                on_result(&self.totals, result);
+            }
+
+            start = end + 1;
+            scan_from = start;
+        }
+
+        pending.drain(..start);
+        self.pending = pending;
+        self.scanned = self.pending.len();
+    }
+
+    /// Evaluates whatever is left once the input is known to be over, even
+    /// without a final newline.
+    pub fn finish(&mut self, mut on_result: impl FnMut(&RunningTotals, LineResult)) {
+        let line = std::mem::take(&mut self.pending);
+        self.scanned = 0;
+
+        if let Some(result) = self.evaluate_line(&line) {
+            on_result(&self.totals, result);
+        }
+    }
+
+    fn evaluate_line(&mut self, line: &[u8]) -> Option<LineResult> {
+        self.line += 1;
+
+        let line = String::from_utf8_lossy(line);
+        if line.trim().is_empty() {
+            return None;
+        }
+
+        return Some(match parse_line_with(&line, &self.options) {
+            Ok(game) => {
+                self.add(&game);
+                Ok(game.id)
+            }
+            Err(error) => Err(LineError {
+                line: self.line,
+                error,
+            }),
+        });
+    }
+
+    fn add(&mut self, game: &Game) {
+        let minimum_bag = game.minimum_bag();
+
+        self.totals.games += 1;
+        if minimum_bag.fits_in(&self.bag) {
+            self.totals.feasible_id_sum = self.totals.feasible_id_sum.saturating_add(game.id);
+        }
+        self.totals.power_sum = self.totals.power_sum.saturating_add(minimum_bag.power());
+    }
+}
+
+/// Reads `reader` until it runs dry, passing every evaluated line to
+/// `on_result`. With `follow`, running dry means waiting that long and trying
+/// again, like `tail -f`, so it only returns on a read error. Interrupted
+/// reads are retried.
+///
+/// Every distinct colour name gets interned for good (see `Color`), so
+/// following a log that keeps inventing new colour names grows memory without
+/// bound.
+pub fn evaluate_game_log(
+    mut reader: impl Read,
+    evaluator: &mut GameLogEvaluator,
+    follow: Option<Duration>,
+    mut on_result: impl FnMut(&RunningTotals, LineResult),
+) -> io::Result<()> {
+    let mut buffer = [0; 8 * 1024];
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(read) => read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+
+        if read == 0 {
+            match follow {
+                Some(poll) => {
+                    thread::sleep(poll);
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        evaluator.feed(&buffer[..read], &mut on_result);
+    }
+
+    evaluator.finish(&mut on_result);
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor, Read};
+
+    use crate::day2::{get_puzzle_bag, tests::EXAMPLE, LineError, ParseGameError, ParseOptions};
+
+    use super::{evaluate_game_log, GameLogEvaluator, LineResult, RunningTotals};
+
+    fn feed(evaluator: &mut GameLogEvaluator, bytes: &[u8]) -> Vec<LineResult> {
+        let mut results = vec![];
+        evaluator.feed(bytes, |_, result| results.push(result));
+
+        return results;
+    }
+
+    #[test]
+    fn test_feed_holds_partial_lines() {
+        let mut evaluator = GameLogEvaluator::new(get_puzzle_bag(), ParseOptions::default());
+
+        assert_eq!(feed(&mut evaluator, b"Game 1: 3 blue, 4 "), vec![]);
+        assert_eq!(feed(&mut evaluator, b"red\nGame 2: 1 blue"), vec![Ok(1)]);
+        assert_eq!(
+            evaluator.totals(),
+            &RunningTotals {
+                games: 1,
+                feasible_id_sum: 1,
+                power_sum: 12,
+            }
+        );
+
+        assert_eq!(feed(&mut evaluator, b", 2 green\n\n"), vec![Ok(2)]);
+        evaluator.finish(|_, result| panic!("unexpected {:?}", result));
+        assert_eq!(evaluator.totals().feasible_id_sum, 3);
+    }
+
+    #[test]
+    fn test_feed_saturates_totals() {
+        let mut evaluator = GameLogEvaluator::new(get_puzzle_bag(), ParseOptions::default());
+        let input = format!(
+            "Game {}: 1 red\nGame 1: 4294967296 red, 4294967296 blue\nGame 2: 1 red\n",
+            usize::MAX
+        );
+
+        assert_eq!(
+            feed(&mut evaluator, input.as_bytes()),
+            vec![Ok(usize::MAX), Ok(1), Ok(2)]
+        );
+        assert_eq!(
+            evaluator.totals(),
+            &RunningTotals {
+                games: 3,
+                feasible_id_sum: usize::MAX,
+                power_sum: usize::MAX,
+            }
+        );
+    }
+
+    #[test]
+    fn test_feed_byte_by_byte() {
+        let mut evaluator = GameLogEvaluator::new(get_puzzle_bag(), ParseOptions::default());
+
+        let mut ids = vec![];
+        // Without the final newline, the last game only ends at `finish`.
+        for byte in EXAMPLE.trim_end().as_bytes() {
+            ids.extend(feed(&mut evaluator, &[*byte]));
+        }
+        evaluator.finish(|_, result| ids.push(result));
+
+        assert_eq!(ids, vec![Ok(1), Ok(2), Ok(3), Ok(4), Ok(5)]);
+        assert_eq!(
+            evaluator.totals(),
+            &RunningTotals {
+                games: 5,
+                feasible_id_sum: 8,
+                power_sum: 2286,
+            }
+        );
+    }
+
+    #[test]
+    fn test_evaluate_game_log_retries_interrupted_reads() {
+        struct Interrupting(Cursor<&'static str>, bool);
+
+        impl Read for Interrupting {
+            fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+                self.1 = !self.1;
+                if self.1 {
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+
+                return self.0.read(&mut buffer[..4]);
+            }
+        }
+
+        let mut evaluator = GameLogEvaluator::new(get_puzzle_bag(), ParseOptions::default());
+        let mut ids = vec![];
+        evaluate_game_log(
+            Interrupting(Cursor::new(EXAMPLE), false),
+            &mut evaluator,
+            None,
+            |_, result| ids.push(result),
+        )
+        .unwrap();
+
+        assert_eq!(ids, vec![Ok(1), Ok(2), Ok(3), Ok(4), Ok(5)]);
+    }
+
+    #[test]
+    fn test_evaluate_game_log() {
+        let input = format!("{}Game 6 3 red\n", EXAMPLE.replace('\n', "\r\n"));
+        let mut evaluator = GameLogEvaluator::new(get_puzzle_bag(), ParseOptions::default());
+
+        let mut results = vec![];
+        evaluate_game_log(
+            Cursor::new(input),
+            &mut evaluator,
+            None,
+            |totals, result| results.push((result, totals.clone())),
+        )
+        .unwrap();
+
+        assert_eq!(results.len(), 6);
+        assert_eq!(
+            results[4],
+            (
+                Ok(5),
+                RunningTotals {
+                    games: 5,
+                    feasible_id_sum: 8,
+                    power_sum: 2286,
+                }
+            )
+        );
+        assert_eq!(
+            results[5].0,
+            Err(LineError {
+                line: 6,
+                error: ParseGameError::MissingHeader("Game 6 3 red".to_string()),
+            })
+        );
+    }
+}
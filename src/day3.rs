@@ -1,261 +1,720 @@
-use std::fs;
+use std::{error::Error, fs, str::FromStr};
 
-#[derive(Debug, PartialEq)]
-struct Matrix<T> {
-    pub data: Vec<Vec<T>>,
+use serde::Serialize;
+
+use crate::grid::{Grid, Point, Rgb};
+
+mod graph;
+mod render;
+
+/// A run of digits on one row; `col_end` is exclusive.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NumberSpan {
+    /// Saturates at `u64::MAX` for runs too long to fit.
+    pub value: u64,
+    pub row: usize,
+    pub col_start: usize,
+    pub col_end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Symbol {
+    pub ch: char,
+    pub point: Point,
+}
+
+/// A schematic split into numbers and symbols, with each digit cell pointing
+/// back at the number it belongs to.
+#[derive(Debug)]
+pub struct Schematic {
+    pub grid: Grid<char>,
+    pub numbers: Vec<NumberSpan>,
+    pub symbols: Vec<Symbol>,
+    blanks: Vec<char>,
+    span_index: Grid<Option<usize>>,
+}
+
+/// Which symbols a rule looks at.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolSet {
+    Any,
+    Only(Vec<char>),
+}
+
+/// How many distinct numbers a symbol must touch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NeighbourCount {
+    Exactly(usize),
+    AtLeast(usize),
+    /// Inclusive on both ends.
+    Between(usize, usize),
 }
 
-impl<T> Matrix<T> {
-    pub fn new(input: Vec<Vec<T>>) -> Self {
-        Matrix { data: input }
+/// How the numbers around a matching symbol combine into its value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reduction {
+    Product,
+    Sum,
+    Max,
+}
+
+/// Picks out symbols by character and by how many numbers touch them, and
+/// reduces those numbers to one value per symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GearRule {
+    pub symbols: SymbolSet,
+    pub count: NeighbourCount,
+    pub reduction: Reduction,
+}
+
+impl NumberSpan {
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        return (self.col_start..self.col_end).map(|col| Point::new(self.row, col));
     }
+}
 
-    pub fn get(&self, x: isize, y: isize) -> Option<&T> {
-        if x < 0 || y < 0 {
-            return None;
+impl SymbolSet {
+    pub fn contains(&self, ch: char) -> bool {
+        return match self {
+            SymbolSet::Any => true,
+            SymbolSet::Only(symbols) => symbols.contains(&ch),
+        };
+    }
+}
+
+impl FromStr for SymbolSet {
+    type Err = Box<dyn Error>;
+
+    /// `any`, or the symbol characters written together, like `*#`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "any" {
+            return Ok(SymbolSet::Any);
         }
-        let row = self.data.get(x as usize)?;
-        let col = row.get(y as usize)?;
-        return Some(col);
+        if s.is_empty() {
+            return Err("no symbols given".into());
+        }
+
+        return Ok(SymbolSet::Only(s.chars().collect()));
     }
 }
 
-#[allow(dead_code)]
-pub fn run() {
-    run_part_2();
+impl NeighbourCount {
+    pub fn allows(&self, count: usize) -> bool {
+        return match *self {
+            NeighbourCount::Exactly(n) => count == n,
+            NeighbourCount::AtLeast(n) => count >= n,
+            NeighbourCount::Between(min, max) => (min..=max).contains(&count),
+        };
+    }
 }
 
-#[allow(dead_code)]
-fn run_part_2() {
-    let contents = fs::read_to_string("input/day3.txt").unwrap();
-    let matrix: Vec<Vec<_>> = contents
-        .split("\n")
-        .map(|line| line.chars().collect())
-        .collect();
-
-    let mut checked_surrounds: Vec<(usize, usize)> = vec![];
-
-    let mut sum = 0;
-    for y in 0..matrix.len() {
-        for x in 0..matrix[y].len() {
-            if matrix[y][x] == '*' {
-                let surrounding_numbers =
-                    get_surrounding_numers(&mut checked_surrounds, &matrix, x, y);
-
-                println!(
-                    "({},{}) has {:?} surrounding numbers",
-                    x, y, surrounding_numbers
-                );
-                if surrounding_numbers.len() == 2 {
-                    sum += surrounding_numbers[0] * surrounding_numbers[1];
-                }
+impl FromStr for NeighbourCount {
+    type Err = Box<dyn Error>;
+
+    /// `2` for exactly two, `2+` for at least two, `1-3` for one to three.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(min) = s.strip_suffix('+') {
+            return Ok(NeighbourCount::AtLeast(min.parse()?));
+        }
+        if let Some((min, max)) = s.split_once('-') {
+            let (min, max) = (min.parse()?, max.parse()?);
+            if min > max {
+                return Err(format!("empty range {}", s).into());
             }
+
+            return Ok(NeighbourCount::Between(min, max));
         }
+
+        return Ok(NeighbourCount::Exactly(s.parse()?));
     }
+}
 
-    println!("result: {}", sum);
+impl Reduction {
+    /// Reduces `values`, saturating at `u64::MAX`; an empty product is 1,
+    /// an empty sum or max 0.
+    pub fn apply(&self, values: impl Iterator<Item = u64>) -> u64 {
+        return match self {
+            Reduction::Product => values.fold(1, u64::saturating_mul),
+            Reduction::Sum => values.fold(0, u64::saturating_add),
+            Reduction::Max => values.max().unwrap_or(0),
+        };
+    }
 }
 
-fn get_surrounding_numers(
-    cells_visited: &mut Vec<(usize, usize)>,
-    matrix: &Vec<Vec<char>>,
-    x: usize,
-    y: usize,
-) -> Vec<i32> {
-    let directions = vec![
-        (-1, -1),
-        (-1, 0),
-        (-1, 1),
-        (0, -1),
-        (0, 1),
-        (1, -1),
-        (1, 0),
-        (1, 1),
-    ];
-    let mut surrounding_numbers: Vec<i32> = vec![];
-    for direction in directions {
-        let current_x = x as isize + direction.0;
-        let current_y = y as isize + direction.1;
-
-        if current_x < 0 || current_y < 0 {
-            continue;
-        }
-        if current_y > matrix.len() as isize
-            || current_x > matrix[current_y as usize].len() as isize
-        {
-            continue;
-        }
-        let current_x = current_x as usize;
-        let current_y = current_y as usize;
+impl FromStr for Reduction {
+    type Err = Box<dyn Error>;
 
-        if cells_visited.contains(&(current_x, current_y)) {
-            continue;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "product" => Ok(Reduction::Product),
+            "sum" => Ok(Reduction::Sum),
+            "max" => Ok(Reduction::Max),
+            _ => Err(format!("unknown reduction \"{}\"", s).into()),
+        };
+    }
+}
+
+impl GearRule {
+    pub fn part_2() -> Self {
+        GearRule {
+            symbols: SymbolSet::Only(vec!['*']),
+            count: NeighbourCount::Exactly(2),
+            reduction: Reduction::Product,
         }
+    }
+}
 
-        if matrix[current_y][current_x].is_ascii_digit() {
-            let mut consuming = "".to_string();
-            let mut x_offset = 0;
-            while let Some(ch) = matrix[current_y].get((current_x as isize + x_offset) as usize) {
-                println!("Looking at {} at ({},{})", ch, current_x, current_y);
-                if ch.is_ascii_digit() {
-                    cells_visited.push(((current_x as isize + x_offset) as usize, current_y));
-                    x_offset += 1;
-                    consuming.push(*ch);
-                } else {
-                    break;
-                }
-            }
-            x_offset = -1;
-            while let Some(ch) = matrix[current_y].get((current_x as isize + x_offset) as usize) {
-                println!("Looking at {} at ({},{})", ch, current_x, current_y);
-                if ch.is_ascii_digit() {
-                    cells_visited.push(((current_x as isize + x_offset) as usize, current_y));
-                    x_offset -= 1;
-                    consuming.insert(0, *ch);
-                } else {
-                    break;
+impl Schematic {
+    #[allow(dead_code)]
+    pub fn new(grid: Grid<char>) -> Self {
+        return Schematic::with_blanks(grid, &['.']);
+    }
+
+    /// Like `new`, but with `blanks` instead of `.` as the characters that
+    /// are neither digits nor symbols.
+    pub fn with_blanks(grid: Grid<char>, blanks: &[char]) -> Self {
+        let mut numbers: Vec<NumberSpan> = vec![];
+        let mut symbols = vec![];
+        let mut span_index = grid.map(|_| None);
+
+        for (point, &ch) in grid.iter() {
+            if let Some(digit) = ch.to_digit(10) {
+                let continues = numbers
+                    .last()
+                    .is_some_and(|span| span.row == point.row && span.col_end == point.col);
+                if !continues {
+                    numbers.push(NumberSpan {
+                        value: 0,
+                        row: point.row,
+                        col_start: point.col,
+                        col_end: point.col,
+                    });
                 }
+
+                let span = numbers.last_mut().unwrap();
+                span.value = span
+                    .value
+                    .checked_mul(10)
+                    .and_then(|value| value.checked_add(digit as u64))
+                    .unwrap_or(u64::MAX);
+                span.col_end += 1;
+                span_index[point] = Some(numbers.len() - 1);
+            } else if !blanks.contains(&ch) {
+                symbols.push(Symbol { ch, point });
             }
-            surrounding_numbers.push(consuming.parse::<i32>().unwrap());
         }
+
+        return Schematic {
+            grid,
+            numbers,
+            symbols,
+            blanks: blanks.to_vec(),
+            span_index,
+        };
+    }
+
+    /// The index into `numbers` of the number covering `point`, if any.
+    pub fn span_at(&self, point: Point) -> Option<usize> {
+        return self.span_index.get(point).copied().flatten();
+    }
+
+    /// Indices of the distinct numbers touching `point`, in reading order.
+    pub fn numbers_around(&self, point: Point) -> Vec<usize> {
+        let mut spans: Vec<usize> = self
+            .grid
+            .neighbours8(point)
+            .filter_map(|neighbour| self.span_at(neighbour))
+            .collect();
+        spans.sort();
+        spans.dedup();
+
+        return spans;
+    }
+
+    pub fn is_symbol(&self, ch: char) -> bool {
+        return !ch.is_ascii_digit() && !self.blanks.contains(&ch);
+    }
+
+    pub fn has_symbol_around(&self, point: Point, symbols: &SymbolSet) -> bool {
+        return self.grid.neighbours8(point).any(|neighbour| {
+            let ch = self.grid[neighbour];
+            self.is_symbol(ch) && symbols.contains(ch)
+        });
     }
 
-    return surrounding_numbers;
+    pub fn is_part_number(&self, span: &NumberSpan, symbols: &SymbolSet) -> bool {
+        return span
+            .points()
+            .any(|point| self.has_symbol_around(point, symbols));
+    }
+
+    /// Saturates at `u64::MAX`.
+    pub fn sum_part_numbers(&self, symbols: &SymbolSet) -> u64 {
+        return self
+            .numbers
+            .iter()
+            .filter(|span| self.is_part_number(span, symbols))
+            .map(|span| span.value)
+            .fold(0, u64::saturating_add);
+    }
+
+    /// The symbols matching `rule` with their reduced values.
+    pub fn find_gears<'a>(&'a self, rule: &'a GearRule) -> impl Iterator<Item = (&'a Symbol, u64)> {
+        return self
+            .symbols
+            .iter()
+            .filter(|symbol| rule.symbols.contains(symbol.ch))
+            .filter_map(|symbol| {
+                let spans = self.numbers_around(symbol.point);
+                if !rule.count.allows(spans.len()) {
+                    return None;
+                }
+
+                let value = rule
+                    .reduction
+                    .apply(spans.iter().map(|&span| self.numbers[span].value));
+                return Some((symbol, value));
+            });
+    }
+
+    /// Saturates at `u64::MAX`.
+    pub fn sum_gears(&self, rule: &GearRule) -> u64 {
+        return self
+            .find_gears(rule)
+            .map(|(_, value)| value)
+            .fold(0, u64::saturating_add);
+    }
 }
 
 #[allow(dead_code)]
-fn run_part_1() {
+pub fn run() {
+    run_part_2();
+}
+
+pub fn run_command(args: &[String]) {
+    run_with_blanks(args, &['.']);
+}
+
+fn run_with_blanks(args: &[String], blanks: &[char]) {
+    match args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>()[..] {
+        ["--blank", blanks, ..] => run_with_blanks(&args[2..], &blanks.chars().collect::<Vec<_>>()),
+        ["parts"] => run_parts(&SymbolSet::Any, blanks),
+        ["parts", symbols] => run_parts(
+            &symbols
+                .parse()
+                .unwrap_or_else(|error| panic!("invalid symbols: {}", error)),
+            blanks,
+        ),
+        ["gears", symbols, count, reduction] => run_gears(
+            &GearRule {
+                symbols: symbols
+                    .parse()
+                    .unwrap_or_else(|error| panic!("invalid symbols: {}", error)),
+                count: count
+                    .parse()
+                    .unwrap_or_else(|error| panic!("invalid count: {}", error)),
+                reduction: reduction
+                    .parse()
+                    .unwrap_or_else(|error| panic!("invalid reduction: {}", error)),
+            },
+            blanks,
+        ),
+        ["graph", "dot"] => print!(
+            "{}",
+            graph::SchematicGraph::new(&read_schematic(blanks)).to_dot()
+        ),
+        ["graph", "json"] => println!(
+            "{}",
+            graph::SchematicGraph::new(&read_schematic(blanks)).to_json()
+        ),
+        ["components"] => run_components(blanks),
+        ["image", path] => run_image(path, 4, blanks),
+        ["image", path, cell_size] => run_image(
+            path,
+            cell_size.parse().expect("cell size must be a number"),
+            blanks,
+        ),
+        ["render"] => run_render(None, blanks),
+        ["render", row, col, height, width] => run_render(
+            Some(render::Viewport {
+                row: row.parse().expect("row must be a number"),
+                col: col.parse().expect("col must be a number"),
+                height: height.parse().expect("height must be a number"),
+                width: width.parse().expect("width must be a number"),
+            }),
+            blanks,
+        ),
+        _ => run(),
+    }
+}
+
+fn read_schematic(blanks: &[char]) -> Schematic {
     let contents = fs::read_to_string("input/day3.txt").unwrap();
-    let matrix = parse_input(contents);
-
-    let mut numbers: Vec<usize> = vec![];
-    let mut consuming = "".to_string();
-    let mut has_around = false;
-    for (x, _) in matrix.data.iter().enumerate() {
-        if let Some(row) = matrix.data.get(x) {
-            for (y, _) in row.iter().enumerate() {
-                if let Some(_) = row.get(y) {
-                    if let Some(current_digit) = matrix.get(x as isize, y as isize) {
-                        if current_digit.is_ascii_digit() {
-                            consuming.push(*current_digit);
-                            has_around =
-                                has_around || has_symbol_around(&matrix, x as isize, y as isize);
-                        } else {
-                            if consuming.len() > 0 && has_around {
-                                println!("{} has around", consuming);
-                                numbers.push(consuming.parse::<usize>().unwrap());
-                            }
-                            consuming = "".to_string();
-                            has_around = false;
-                        }
-                    }
-                }
-            }
-            if consuming.len() > 0 && has_around {
-                println!("{} has around", consuming);
-                numbers.push(consuming.parse::<usize>().unwrap());
-            }
-            consuming = "".to_string();
-            has_around = false;
+
+    return parse_schematic(&contents, blanks);
+}
+
+/// Parses a schematic, padding ragged rows with a character that is blank for
+/// it: the first non-digit in `blanks`, or else one the input never uses,
+/// which then joins the blanks. Padding never adds digits or symbols.
+fn parse_schematic(contents: &str, blanks: &[char]) -> Schematic {
+    let mut blanks = blanks.to_vec();
+    let fill = match blanks.iter().find(|ch| !ch.is_ascii_digit()) {
+        Some(&fill) => fill,
+        None => {
+            let unused = [' ', '.']
+                .into_iter()
+                .chain('\u{E000}'..='\u{F8FF}')
+                .find(|&ch| !contents.contains(ch))
+                .expect("schematic uses every padding character");
+            blanks.push(unused);
+            unused
         }
+    };
+
+    return Schematic::with_blanks(parse_input_with_fill(contents, fill), &blanks);
+}
+
+fn run_parts(symbols: &SymbolSet, blanks: &[char]) {
+    println!(
+        "sum is {}",
+        read_schematic(blanks).sum_part_numbers(symbols)
+    );
+}
+
+fn run_gears(rule: &GearRule, blanks: &[char]) {
+    let schematic = read_schematic(blanks);
+
+    let mut count = 0;
+    for (symbol, value) in schematic.find_gears(rule) {
+        println!("'{}' at {}: {}", symbol.ch, symbol.point, value);
+        count += 1;
     }
 
-    println!("sum is {}", numbers.iter().sum::<usize>());
+    println!("{} matches, sum {}", count, schematic.sum_gears(rule));
 }
 
-fn is_symbol(ch: char) -> bool {
-    return !ch.is_ascii_digit() && ch != '.';
+fn run_render(viewport: Option<render::Viewport>, blanks: &[char]) {
+    let schematic = read_schematic(blanks);
+
+    print!(
+        "{}",
+        render::render(&schematic, &GearRule::part_2(), viewport)
+    );
 }
 
-fn has_symbol_around(matrix: &Matrix<char>, x: isize, y: isize) -> bool {
-    let directions: Vec<(isize, isize)> = vec![
-        (-1, -1),
-        (-1, 0),
-        (-1, 1),
-        (0, -1),
-        (0, 1),
-        (1, -1),
-        (1, 0),
-        (1, 1),
-    ];
-
-    let mut result = false;
-    for (dx, dy) in directions {
-        if let Some(ch) = matrix.get(x + dx, y + dy) {
-            if is_symbol(*ch) {
-                result = true;
+/// Writes the schematic as a PPM or BMP image, by `path`'s extension.
+fn run_image(path: &str, cell_size: usize, blanks: &[char]) {
+    let schematic = read_schematic(blanks);
+
+    schematic
+        .grid
+        .write_image(path, cell_size, |&ch| -> Rgb {
+            if ch.is_ascii_digit() {
+                [230, 230, 230]
+            } else if schematic.is_symbol(ch) {
+                [230, 160, 30]
+            } else {
+                [30, 30, 40]
             }
-        }
+        })
+        .unwrap();
+}
+
+/// Prints every component linking more than one number, largest first.
+fn run_components(blanks: &[char]) {
+    let schematic = read_schematic(blanks);
+    let mut components = graph::SchematicGraph::new(&schematic).components();
+    components.retain(|component| component.numbers.len() > 1);
+    components.sort_by_key(|component| std::cmp::Reverse(component.numbers.len()));
+
+    for component in &components {
+        let symbols: String = component
+            .symbols
+            .iter()
+            .map(|&symbol| schematic.symbols[symbol].ch)
+            .collect();
+        let values: Vec<u64> = component
+            .numbers
+            .iter()
+            .map(|&number| schematic.numbers[number].value)
+            .collect();
+
+        println!("{} via \"{}\": {:?}", values.len(), symbols, values);
     }
-    return result;
+    println!("{} linked clusters", components.len());
 }
 
-fn parse_input(contents: String) -> Matrix<char> {
-    return Matrix::new(
-        contents
-            .lines()
-            .map(|line| line.chars().collect())
-            .collect(),
-    );
+#[allow(dead_code)]
+fn run_part_2() {
+    run_gears(&GearRule::part_2(), &['.']);
+}
+
+#[allow(dead_code)]
+fn run_part_1() {
+    run_parts(&SymbolSet::Any, &['.']);
+}
+
+#[cfg(test)]
+fn parse_input(contents: String) -> Grid<char> {
+    return parse_input_with_fill(&contents, '.');
+}
+
+/// Parses a schematic, padding ragged rows with `fill`.
+fn parse_input_with_fill(contents: &str, fill: char) -> Grid<char> {
+    return Grid::parse_padded(contents, fill, Some).unwrap();
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day3::{get_surrounding_numers, has_symbol_around, Matrix};
+    use indoc::indoc;
+
+    use crate::day3::{
+        parse_schematic, GearRule, NeighbourCount, NumberSpan, Reduction, Schematic, Symbol,
+        SymbolSet,
+    };
+    use crate::grid::Point;
 
     use super::parse_input;
 
-    #[test]
-    fn test_matrix_get() {
-        let input = Matrix::new(vec![
-            vec!['1', '2', '3'],
-            vec!['4', '5'],
-            vec!['7', '8', '9'],
-            vec!['a', 'b', 'c'],
-        ]);
-
-        assert_eq!(input.get(0, 0), Some(&'1'));
-        assert_eq!(input.get(0, 1), Some(&'2'));
-        assert_eq!(input.get(0, 2), Some(&'3'));
-        assert_eq!(input.get(0, 3), None);
-        assert_eq!(input.get(1, 0), Some(&'4'));
-        assert_eq!(input.get(1, 1), Some(&'5'));
-        assert_eq!(input.get(1, 2), None);
-        assert_eq!(input.get(2, 0), Some(&'7'));
-        assert_eq!(input.get(2, 1), Some(&'8'));
-        assert_eq!(input.get(2, 2), Some(&'9'));
-        assert_eq!(input.get(3, 0), Some(&'a'));
-        assert_eq!(input.get(3, 1), Some(&'b'));
-        assert_eq!(input.get(3, 2), Some(&'c'));
-    }
+    const EXAMPLE: &str = indoc! {"
+        467..114..
+        ...*......
+        ..35..633.
+        ......#...
+        617*......
+        .....+.58.
+        ..592.....
+        ......755.
+        ...$.*....
+        .664.598..
+    "};
 
     #[test]
     fn test_has_symbol_around() {
-        let input = parse_input("..#\n...\n...".to_string());
-        assert!(!has_symbol_around(&input, 0, 0));
-        assert!(has_symbol_around(&input, 0, 1));
-        assert!(!has_symbol_around(&input, 0, 2));
+        let input = Schematic::new(parse_input("..#\n...\n...".to_string()));
+        assert!(!input.has_symbol_around(Point::new(0, 0), &SymbolSet::Any));
+        assert!(input.has_symbol_around(Point::new(0, 1), &SymbolSet::Any));
+        assert!(!input.has_symbol_around(Point::new(0, 2), &SymbolSet::Any));
 
-        assert!(!has_symbol_around(&input, 1, 0));
-        assert!(has_symbol_around(&input, 1, 1));
-        assert!(has_symbol_around(&input, 1, 2));
+        assert!(!input.has_symbol_around(Point::new(1, 0), &SymbolSet::Any));
+        assert!(input.has_symbol_around(Point::new(1, 1), &SymbolSet::Any));
+        assert!(input.has_symbol_around(Point::new(1, 2), &SymbolSet::Any));
 
-        assert!(!has_symbol_around(&input, 2, 0));
-        assert!(!has_symbol_around(&input, 2, 1));
-        assert!(!has_symbol_around(&input, 2, 2));
+        assert!(!input.has_symbol_around(Point::new(2, 0), &SymbolSet::Any));
+        assert!(!input.has_symbol_around(Point::new(2, 1), &SymbolSet::Any));
+        assert!(!input.has_symbol_around(Point::new(2, 2), &SymbolSet::Any));
     }
 
     #[test]
     fn test_get_surrounding_numbers() {
-        let input: Vec<Vec<_>> = "467..1\n...*..\n..35.."
-            .to_string()
-            .lines()
-            .map(|line| line.chars().collect())
+        let schematic = Schematic::new(parse_input("467..1\n...*..\n..35..".to_string()));
+
+        let values: Vec<u64> = schematic
+            .numbers_around(Point::new(1, 3))
+            .iter()
+            .map(|&span| schematic.numbers[span].value)
             .collect();
+        assert_eq!(values, vec![467, 35]);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let schematic = Schematic::new(parse_input("12.#\n.*45\n7..8".to_string()));
+
+        assert_eq!(
+            schematic.numbers,
+            vec![
+                NumberSpan {
+                    value: 12,
+                    row: 0,
+                    col_start: 0,
+                    col_end: 2
+                },
+                NumberSpan {
+                    value: 45,
+                    row: 1,
+                    col_start: 2,
+                    col_end: 4
+                },
+                NumberSpan {
+                    value: 7,
+                    row: 2,
+                    col_start: 0,
+                    col_end: 1
+                },
+                NumberSpan {
+                    value: 8,
+                    row: 2,
+                    col_start: 3,
+                    col_end: 4
+                },
+            ]
+        );
+        assert_eq!(
+            schematic.symbols,
+            vec![
+                Symbol {
+                    ch: '#',
+                    point: Point::new(0, 3)
+                },
+                Symbol {
+                    ch: '*',
+                    point: Point::new(1, 1)
+                },
+            ]
+        );
+        assert_eq!(schematic.span_at(Point::new(1, 3)), Some(1));
+        assert_eq!(schematic.span_at(Point::new(1, 1)), None);
+        assert_eq!(schematic.numbers_around(Point::new(1, 1)), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_tokenize_overlong_number() {
+        let schematic = Schematic::new(parse_input(format!("{}*1", "9".repeat(25))));
 
         assert_eq!(
-            get_surrounding_numers(&mut vec![], &input, 3, 1),
-            vec![467, 35]
+            schematic.numbers[0],
+            NumberSpan {
+                value: u64::MAX,
+                row: 0,
+                col_start: 0,
+                col_end: 25
+            }
+        );
+        assert_eq!(schematic.numbers[1].value, 1);
+    }
+
+    #[test]
+    fn test_example() {
+        let schematic = Schematic::new(parse_input(EXAMPLE.to_string()));
+
+        assert_eq!(schematic.sum_part_numbers(&SymbolSet::Any), 4361);
+        assert_eq!(schematic.sum_gears(&GearRule::part_2()), 467835);
+    }
+
+    #[test]
+    fn test_gear_rules() {
+        let schematic = Schematic::new(parse_input(EXAMPLE.to_string()));
+        let rule = |symbols: &str, count: &str, reduction: &str| GearRule {
+            symbols: symbols.parse().unwrap(),
+            count: count.parse().unwrap(),
+            reduction: reduction.parse().unwrap(),
+        };
+
+        assert_eq!(schematic.sum_gears(&rule("*", "2", "product")), 467835);
+        assert_eq!(
+            schematic.sum_gears(&rule("*", "1+", "sum")),
+            467 + 35 + 617 + 755 + 598
+        );
+        assert_eq!(schematic.sum_gears(&rule("*", "1", "max")), 617);
+        assert_eq!(schematic.sum_gears(&rule("#$", "1-2", "max")), 633 + 664);
+        assert_eq!(schematic.sum_gears(&rule("any", "0", "product")), 0);
+        assert_eq!(schematic.sum_part_numbers(&"#".parse().unwrap()), 633);
+
+        assert_eq!(
+            "3+".parse::<NeighbourCount>().unwrap(),
+            NeighbourCount::AtLeast(3)
+        );
+        assert_eq!(
+            "1-3".parse::<NeighbourCount>().unwrap(),
+            NeighbourCount::Between(1, 3)
+        );
+        assert!("3-1".parse::<NeighbourCount>().is_err());
+        assert!("two".parse::<NeighbourCount>().is_err());
+        assert!("mean".parse::<Reduction>().is_err());
+        assert!("".parse::<SymbolSet>().is_err());
+    }
+
+    #[test]
+    fn test_gear_values_saturate() {
+        let schematic = Schematic::new(parse_input("9999999999*9999999999".to_string()));
+        assert_eq!(schematic.sum_gears(&GearRule::part_2()), u64::MAX);
+
+        let schematic = Schematic::new(parse_input(format!("{}*{}", u64::MAX, u64::MAX)));
+        assert_eq!(schematic.sum_part_numbers(&SymbolSet::Any), u64::MAX);
+        assert_eq!(Reduction::Sum.apply([u64::MAX, 1].into_iter()), u64::MAX);
+    }
+
+    #[test]
+    fn test_gears_on_edges_and_corners() {
+        let tests = vec![
+            // Corners.
+            "*2\n3.",
+            "2*\n.3",
+            "3.\n*2",
+            ".3\n2*",
+            // Edges.
+            "2*3\n...\n...",
+            "...\n...\n2*3",
+            "2..\n*..\n3..",
+            "..2\n..*\n..3",
+            // The last row and column of a wider grid.
+            "......\n....2.\n....3*",
+        ];
+
+        for input in tests {
+            let schematic = Schematic::new(parse_input(input.to_string()));
+            assert_eq!(schematic.sum_gears(&GearRule::part_2()), 6, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_ragged_and_trailing_newlines() {
+        let tests = vec![
+            "467..\n..*\n.35",
+            "467..\n..*\n.35\n",
+            "467..\n..*\n.35\n\n",
+            "467\r\n..*\r\n.35\r\n",
+            "467\n...*\n..35",
+        ];
+
+        for input in tests {
+            let schematic = Schematic::new(parse_input(input.to_string()));
+            assert_eq!(
+                schematic.sum_gears(&GearRule::part_2()),
+                467 * 35,
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_padding_is_blank() {
+        let tests = vec![
+            ("12\n3", vec![], 0),
+            ("12\n3", vec!['1'], 0),
+            ("1 \n2", vec![], 3),
+            ("1.\n2", vec![], 3),
+            ("1.\n2", vec!['.'], 0),
+        ];
+
+        for (input, blanks, expected) in tests {
+            assert_eq!(
+                parse_schematic(input, &blanks).sum_part_numbers(&SymbolSet::Any),
+                expected,
+                "{:?} with blanks {:?}",
+                input,
+                blanks
+            );
+        }
+    }
+
+    #[test]
+    fn test_blanks() {
+        let grid = parse_input("1 2\n.*.".to_string());
+
+        assert_eq!(
+            Schematic::new(grid.clone()).sum_part_numbers(&SymbolSet::Any),
+            3
+        );
+        assert_eq!(
+            Schematic::with_blanks(grid, &[' ', '.']).symbols,
+            vec![Symbol {
+                ch: '*',
+                point: Point::new(1, 1)
+            }]
         );
     }
 }
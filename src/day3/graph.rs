@@ -0,0 +1,202 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use super::{NumberSpan, Schematic, Symbol};
+
+/// An edge between `symbols[symbol]` and `numbers[number]` of the schematic.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Edge {
+    pub symbol: usize,
+    pub number: usize,
+}
+
+/// Symbols and numbers that reach each other through adjacency, by index.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Component {
+    pub symbols: Vec<usize>,
+    pub numbers: Vec<usize>,
+}
+
+/// The bipartite graph with an edge between every symbol and each distinct
+/// number touching it.
+#[derive(Debug, Serialize)]
+pub struct SchematicGraph<'a> {
+    pub symbols: &'a [Symbol],
+    pub numbers: &'a [NumberSpan],
+    pub edges: Vec<Edge>,
+}
+
+impl<'a> SchematicGraph<'a> {
+    pub fn new(schematic: &'a Schematic) -> Self {
+        let edges = schematic
+            .symbols
+            .iter()
+            .enumerate()
+            .flat_map(|(symbol, Symbol { point, .. })| {
+                schematic
+                    .numbers_around(*point)
+                    .into_iter()
+                    .map(move |number| Edge { symbol, number })
+            })
+            .collect();
+
+        return SchematicGraph {
+            symbols: &schematic.symbols,
+            numbers: &schematic.numbers,
+            edges,
+        };
+    }
+
+    /// Every connected component, lone symbols and numbers included, ordered
+    /// by their first symbol and then by their first number.
+    pub fn components(&self) -> Vec<Component> {
+        // Symbols are nodes 0..symbols.len(), numbers come after them.
+        let offset = self.symbols.len();
+        let mut parents: Vec<usize> = (0..offset + self.numbers.len()).collect();
+
+        fn find(parents: &mut [usize], mut node: usize) -> usize {
+            while parents[node] != node {
+                parents[node] = parents[parents[node]];
+                node = parents[node];
+            }
+            return node;
+        }
+
+        for edge in &self.edges {
+            let a = find(&mut parents, edge.symbol);
+            let b = find(&mut parents, offset + edge.number);
+            parents[a.max(b)] = a.min(b);
+        }
+
+        let mut components: Vec<Component> = vec![];
+        let mut component_of_root = vec![None; parents.len()];
+        for node in 0..parents.len() {
+            let root = find(&mut parents, node);
+            let index = *component_of_root[root].get_or_insert_with(|| {
+                components.push(Component {
+                    symbols: vec![],
+                    numbers: vec![],
+                });
+                components.len() - 1
+            });
+
+            if node < offset {
+                components[index].symbols.push(node);
+            } else {
+                components[index].numbers.push(node - offset);
+            }
+        }
+
+        return components;
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = "graph schematic {\n".to_string();
+
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            let label = symbol
+                .ch
+                .to_string()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            writeln!(dot, "  s{} [label=\"{}\" shape=box];", index, label).unwrap();
+        }
+        for (index, number) in self.numbers.iter().enumerate() {
+            writeln!(dot, "  n{} [label=\"{}\"];", index, number.value).unwrap();
+        }
+        for edge in &self.edges {
+            writeln!(dot, "  s{} -- n{};", edge.symbol, edge.number).unwrap();
+        }
+        dot.push_str("}\n");
+
+        return dot;
+    }
+
+    /// The nodes and edges, plus the components under `"components"`.
+    pub fn to_json(&self) -> String {
+        #[derive(Serialize)]
+        struct Export<'a> {
+            #[serde(flatten)]
+            graph: &'a SchematicGraph<'a>,
+            components: Vec<Component>,
+        }
+
+        return serde_json::to_string_pretty(&Export {
+            graph: self,
+            components: self.components(),
+        })
+        .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day3::{parse_input, Schematic};
+
+    use super::{Component, Edge, SchematicGraph};
+
+    #[test]
+    fn test_edges_and_components() {
+        // `1` and `2` share the `*`, `2` and `3` share the `#`; `4` is alone.
+        let schematic = Schematic::new(parse_input("1*2.4\n..#..\n.3...".to_string()));
+        let graph = SchematicGraph::new(&schematic);
+
+        assert_eq!(
+            graph.edges,
+            vec![
+                Edge {
+                    symbol: 0,
+                    number: 0
+                },
+                Edge {
+                    symbol: 0,
+                    number: 1
+                },
+                Edge {
+                    symbol: 1,
+                    number: 1
+                },
+                Edge {
+                    symbol: 1,
+                    number: 3
+                },
+            ]
+        );
+        assert_eq!(
+            graph.components(),
+            vec![
+                Component {
+                    symbols: vec![0, 1],
+                    numbers: vec![0, 1, 3]
+                },
+                Component {
+                    symbols: vec![],
+                    numbers: vec![2]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exports() {
+        let schematic = Schematic::new(parse_input("7\"\n..".to_string()));
+        let graph = SchematicGraph::new(&schematic);
+
+        assert_eq!(
+            graph.to_dot(),
+            "graph schematic {\n  s0 [label=\"\\\"\" shape=box];\n  n0 [label=\"7\"];\n  s0 -- n0;\n}\n"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&graph.to_json()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "symbols": [{"ch": "\"", "point": {"row": 0, "col": 1}}],
+                "numbers": [{"value": 7, "row": 0, "col_start": 0, "col_end": 1}],
+                "edges": [{"symbol": 0, "number": 0}],
+                "components": [{"symbols": [0], "numbers": [0]}],
+            })
+        );
+    }
+}
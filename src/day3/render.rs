@@ -0,0 +1,142 @@
+use std::collections::HashSet;
+
+use crate::grid::Point;
+
+use super::{GearRule, Schematic, SymbolSet};
+
+const RESET: &str = "\x1b[0m";
+const RED: &str = "31";
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+const UNDERLINE: &str = "4";
+
+/// A window of `height` rows and `width` columns with its top-left corner at
+/// (`row`, `col`). Parts falling outside the grid are ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub row: usize,
+    pub col: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+/// Renders the schematic with ANSI colours: part numbers green, other
+/// numbers red, symbols matching `gears` yellow and the numbers linked to
+/// them underlined.
+pub fn render(schematic: &Schematic, gears: &GearRule, viewport: Option<Viewport>) -> String {
+    let grid = &schematic.grid;
+    let viewport = viewport.unwrap_or(Viewport {
+        row: 0,
+        col: 0,
+        height: grid.height(),
+        width: grid.width(),
+    });
+
+    let gear_points: HashSet<Point> = schematic
+        .find_gears(gears)
+        .map(|(symbol, _)| symbol.point)
+        .collect();
+    let geared_spans: HashSet<usize> = gear_points
+        .iter()
+        .flat_map(|&point| schematic.numbers_around(point))
+        .collect();
+    let part_spans: Vec<bool> = schematic
+        .numbers
+        .iter()
+        .map(|span| schematic.is_part_number(span, &SymbolSet::Any))
+        .collect();
+
+    let mut output = String::new();
+    let rows = viewport.row
+        ..viewport
+            .row
+            .saturating_add(viewport.height)
+            .min(grid.height());
+    for row in rows {
+        let mut current_style = None;
+
+        let cols = viewport.col
+            ..viewport
+                .col
+                .saturating_add(viewport.width)
+                .min(grid.width());
+        for col in cols {
+            let point = Point::new(row, col);
+            let style = match schematic.span_at(point) {
+                Some(span) if geared_spans.contains(&span) => {
+                    Some(format!("{};{}", GREEN, UNDERLINE))
+                }
+                Some(span) if part_spans[span] => Some(GREEN.to_string()),
+                Some(_) => Some(RED.to_string()),
+                None if gear_points.contains(&point) => Some(YELLOW.to_string()),
+                None => None,
+            };
+
+            if style != current_style {
+                if current_style.is_some() {
+                    output.push_str(RESET);
+                }
+                if let Some(style) = &style {
+                    output.push_str(&format!("\x1b[{}m", style));
+                }
+                current_style = style;
+            }
+            output.push(grid[point]);
+        }
+
+        if current_style.is_some() {
+            output.push_str(RESET);
+        }
+        output.push('\n');
+    }
+
+    return output;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day3::{parse_input, GearRule, Schematic};
+
+    use super::{render, Viewport};
+
+    #[test]
+    fn test_render() {
+        let schematic = Schematic::new(parse_input("12*3\n....\n4#.5".to_string()));
+
+        assert_eq!(
+            render(&schematic, &GearRule::part_2(), None),
+            concat!(
+                "\x1b[32;4m12\x1b[0m\x1b[33m*\x1b[0m\x1b[32;4m3\x1b[0m\n",
+                "....\n",
+                "\x1b[32m4\x1b[0m#.\x1b[31m5\x1b[0m\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_viewport() {
+        let schematic = Schematic::new(parse_input("12*3\n....\n4#.5".to_string()));
+        let viewport = Viewport {
+            row: 1,
+            col: 2,
+            height: 5,
+            width: 1,
+        };
+
+        assert_eq!(
+            render(&schematic, &GearRule::part_2(), Some(viewport)),
+            ".\n.\n"
+        );
+
+        let unbounded = Viewport {
+            row: 2,
+            col: 1,
+            height: usize::MAX,
+            width: usize::MAX,
+        };
+        assert_eq!(
+            render(&schematic, &GearRule::part_2(), Some(unbounded)),
+            "#.\x1b[31m5\x1b[0m\n"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::fs;
 
+#[allow(dead_code)]
 pub fn run() {
     run_part_2();
 }
@@ -93,7 +94,7 @@ fn run_part_2() {
 fn line_to_card(line: String) -> Card {
     let parts: Vec<_> = line.split(": ").map(|part| part.trim()).collect();
     let id = parts
-        .get(0)
+        .first()
         .unwrap()
         .replace("Card ", "")
         .trim()
@@ -138,8 +138,8 @@ impl FromStr for Almanac2 {
 
         let seeds_data: Vec<SeedData> = seeds_data
             .chunks(2)
-            .filter_map(|chunk| match *chunk {
-                [start, length] => Some(SeedData::new(start, length)),
+            .map(|chunk| match *chunk {
+                [start, length] => SeedData::new(start, length),
                 _ => panic!("Invalid input"),
             })
             .collect();
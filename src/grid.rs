@@ -0,0 +1,501 @@
+use std::{
+    error::Error,
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use serde::Serialize;
+
+mod image;
+mod search;
+mod transform;
+
+pub use image::Rgb;
+
+/// A cell position, counted from the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct Point {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Point {
+    pub fn new(row: usize, col: usize) -> Self {
+        return Point { row, col };
+    }
+
+    /// The point `rows` down and `cols` right of this one, or `None` if that
+    /// would be left of or above the origin.
+    pub fn offset(&self, rows: isize, cols: isize) -> Option<Point> {
+        return Some(Point {
+            row: self.row.checked_add_signed(rows)?,
+            col: self.col.checked_add_signed(cols)?,
+        });
+    }
+}
+
+/// Row and column steps to the orthogonal neighbours: up, left, right, down.
+#[allow(dead_code)]
+pub const DIRECTIONS4: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// Row and column steps to all eight neighbours, in reading order.
+pub const DIRECTIONS8: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "({},{})", self.row, self.col);
+    }
+}
+
+/// A rectangular grid stored row by row in a single `Vec`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GridError {
+    /// `cells` doesn't hold exactly `width * height` values, with `expected`
+    /// saturating at `usize::MAX`.
+    SizeMismatch { expected: usize, found: usize },
+    /// A row of the text is a different length from the first one.
+    Ragged {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The conversion closure rejected a character.
+    InvalidCell { point: Point, ch: char },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            GridError::SizeMismatch { expected, found } => {
+                write!(f, "expected {} cells, found {}", expected, found)
+            }
+            GridError::Ragged {
+                row,
+                expected,
+                found,
+            } => write!(f, "row {} has {} cells, expected {}", row, found, expected),
+            GridError::InvalidCell { point, ch } => {
+                write!(f, "invalid cell '{}' at {}", ch, point)
+            }
+        };
+    }
+}
+
+impl Error for GridError {}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Result<Self, GridError> {
+        if width.checked_mul(height) != Some(cells.len()) {
+            return Err(GridError::SizeMismatch {
+                expected: width.saturating_mul(height),
+                found: cells.len(),
+            });
+        }
+
+        return Ok(Grid {
+            width,
+            height,
+            cells,
+        });
+    }
+
+    #[allow(dead_code)]
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        return Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        };
+    }
+
+    /// A grid whose cells are `f` of their point.
+    #[allow(dead_code)]
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Point) -> T) -> Self {
+        let cells = (0..height)
+            .flat_map(|row| (0..width).map(move |col| Point::new(row, col)))
+            .map(&mut f)
+            .collect();
+
+        return Grid {
+            width,
+            height,
+            cells,
+        };
+    }
+
+    /// Parses one cell per character and one row per line, converting each
+    /// character with `convert`.
+    pub fn parse(
+        text: &str,
+        mut convert: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, GridError> {
+        let mut cells = vec![];
+        let mut width = None;
+        let mut height = 0;
+
+        for (row, line) in text.lines().enumerate() {
+            let start = cells.len();
+            for (col, ch) in line.chars().enumerate() {
+                let point = Point::new(row, col);
+                cells.push(convert(ch).ok_or(GridError::InvalidCell { point, ch })?);
+            }
+
+            let found = cells.len() - start;
+            let expected = *width.get_or_insert(found);
+            if found != expected {
+                return Err(GridError::Ragged {
+                    row,
+                    expected,
+                    found,
+                });
+            }
+            height += 1;
+        }
+
+        return Grid::new(width.unwrap_or(0), height, cells);
+    }
+
+    /// Like `parse`, but pads short lines with `fill` up to the longest one
+    /// instead of rejecting them. Empty lines at the end are dropped rather
+    /// than padded into rows.
+    pub fn parse_padded(
+        text: &str,
+        fill: char,
+        convert: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, GridError> {
+        let mut lines: Vec<&str> = text.lines().collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let padded: String = lines
+            .iter()
+            .map(|line| {
+                let padding = width - line.chars().count();
+                format!("{}{}\n", line, fill.to_string().repeat(padding))
+            })
+            .collect();
+
+        return Grid::parse(&padded, convert);
+    }
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        return point.row < self.height && point.col < self.width;
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        if !self.contains(point) {
+            return None;
+        }
+
+        return Some(&self.cells[point.row * self.width + point.col]);
+    }
+
+    #[allow(dead_code)]
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        if !self.contains(point) {
+            return None;
+        }
+
+        return Some(&mut self.cells[point.row * self.width + point.col]);
+    }
+
+    #[allow(dead_code)]
+    pub fn row(&self, row: usize) -> Option<&[T]> {
+        if row >= self.height {
+            return None;
+        }
+
+        return Some(&self.cells[row * self.width..(row + 1) * self.width]);
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        return (0..self.height).map(|row| &self.cells[row * self.width..(row + 1) * self.width]);
+    }
+
+    /// The cells of column `col` from top to bottom; empty if out of bounds.
+    #[allow(dead_code)]
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        let rows = if col < self.width { self.height } else { 0 };
+
+        return (0..rows).map(move |row| &self.cells[row * self.width + col]);
+    }
+
+    #[allow(dead_code)]
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        return (0..self.width).map(|col| self.column(col));
+    }
+
+    /// Every point in row-major order.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+
+        return (0..self.height)
+            .flat_map(move |row| (0..width).map(move |col| Point::new(row, col)));
+    }
+
+    /// Every cell with its point, in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        return self.points().zip(self.cells.iter());
+    }
+
+    /// The in-bounds points one step from `point` in each of `directions`.
+    /// With `wrap`, steps off one edge come back in on the opposite edge,
+    /// so on grids narrower than three cells a point can appear twice.
+    pub fn neighbours<'a>(
+        &self,
+        point: Point,
+        directions: &'a [(isize, isize)],
+        wrap: bool,
+    ) -> impl Iterator<Item = Point> + 'a {
+        let (width, height) = (self.width, self.height);
+        let inside = point.row < height && point.col < width;
+
+        return directions
+            .iter()
+            .filter(move |_| inside)
+            .filter_map(move |&(rows, cols)| {
+                if wrap {
+                    return Some(Point::new(
+                        (point.row as isize + rows).rem_euclid(height as isize) as usize,
+                        (point.col as isize + cols).rem_euclid(width as isize) as usize,
+                    ));
+                }
+
+                return point
+                    .offset(rows, cols)
+                    .filter(|next| next.row < height && next.col < width);
+            });
+    }
+
+    #[allow(dead_code)]
+    pub fn neighbours4(&self, point: Point) -> impl Iterator<Item = Point> {
+        return self.neighbours(point, &DIRECTIONS4, false);
+    }
+
+    pub fn neighbours8(&self, point: Point) -> impl Iterator<Item = Point> {
+        return self.neighbours(point, &DIRECTIONS8, false);
+    }
+
+    #[allow(dead_code)]
+    pub fn wrapping_neighbours4(&self, point: Point) -> impl Iterator<Item = Point> {
+        return self.neighbours(point, &DIRECTIONS4, true);
+    }
+
+    #[allow(dead_code)]
+    pub fn wrapping_neighbours8(&self, point: Point) -> impl Iterator<Item = Point> {
+        return self.neighbours(point, &DIRECTIONS8, true);
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        return Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        };
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &T {
+        assert!(self.contains(point), "{} is outside the grid", point);
+
+        return &self.cells[point.row * self.width + point.col];
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut T {
+        assert!(self.contains(point), "{} is outside the grid", point);
+
+        return &mut self.cells[point.row * self.width + point.col];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, GridError, Point};
+
+    fn letters() -> Grid<char> {
+        return Grid::parse("abc\ndef\nghi\njkl\n", Some).unwrap();
+    }
+
+    #[test]
+    fn test_get() {
+        let grid = letters();
+
+        assert_eq!((grid.width(), grid.height()), (3, 4));
+        assert_eq!(grid.get(Point::new(0, 0)), Some(&'a'));
+        assert_eq!(grid.get(Point::new(0, 2)), Some(&'c'));
+        assert_eq!(grid.get(Point::new(0, 3)), None);
+        assert_eq!(grid.get(Point::new(1, 1)), Some(&'e'));
+        assert_eq!(grid.get(Point::new(3, 0)), Some(&'j'));
+        assert_eq!(grid.get(Point::new(4, 0)), None);
+        assert_eq!(grid[Point::new(2, 1)], 'h');
+        assert_eq!(Point::new(0, 1).offset(-1, 0), None);
+        assert_eq!(Point::new(1, 1).offset(-1, 1), Some(Point::new(0, 2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "(1,3) is outside the grid")]
+    fn test_index_out_of_bounds() {
+        let _ = letters()[Point::new(1, 3)];
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = letters();
+
+        assert_eq!(grid.row(1), Some(&['d', 'e', 'f'][..]));
+        assert_eq!(grid.row(4), None);
+        assert_eq!(grid.rows().count(), 4);
+        assert_eq!(grid.column(1).collect::<String>(), "behk");
+        assert_eq!(grid.column(3).count(), 0);
+        assert_eq!(
+            grid.columns()
+                .map(|column| column.collect::<String>())
+                .collect::<Vec<_>>(),
+            vec!["adgj", "behk", "cfil"]
+        );
+        assert_eq!(grid.iter().nth(4), Some((Point::new(1, 1), &'e')));
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = letters();
+        let points = |neighbours: Vec<Point>| {
+            neighbours
+                .iter()
+                .map(|&point| grid[point])
+                .collect::<String>()
+        };
+
+        assert_eq!(points(grid.neighbours4(Point::new(1, 1)).collect()), "bdfh");
+        assert_eq!(
+            points(grid.neighbours8(Point::new(1, 1)).collect()),
+            "abcdfghi"
+        );
+        assert_eq!(points(grid.neighbours4(Point::new(0, 0)).collect()), "bd");
+        assert_eq!(points(grid.neighbours8(Point::new(3, 2)).collect()), "hik");
+        assert_eq!(grid.neighbours8(Point::new(4, 0)).count(), 0);
+
+        assert_eq!(
+            points(grid.wrapping_neighbours4(Point::new(0, 0)).collect()),
+            "jcbd"
+        );
+        assert_eq!(
+            points(grid.wrapping_neighbours8(Point::new(3, 2)).collect()),
+            "higkjbca"
+        );
+    }
+
+    #[test]
+    fn test_map_and_mutate() {
+        let mut grid = Grid::parse("12\n34", |ch| ch.to_digit(10)).unwrap();
+        grid[Point::new(0, 0)] = 5;
+        *grid.get_mut(Point::new(1, 1)).unwrap() += 1;
+
+        assert_eq!(
+            grid.map(|n| n * 2),
+            Grid::new(2, 2, vec![10, 4, 6, 10]).unwrap()
+        );
+        assert_eq!(grid.get_mut(Point::new(2, 0)), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Grid::parse("abc\nde\nfgh", Some),
+            Err(GridError::Ragged {
+                row: 1,
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            Grid::parse("12\n3x", |ch| ch.to_digit(10)),
+            Err(GridError::InvalidCell {
+                point: Point::new(1, 1),
+                ch: 'x'
+            })
+        );
+        assert_eq!(
+            Grid::new(2, 2, vec![1, 2, 3]),
+            Err(GridError::SizeMismatch {
+                expected: 4,
+                found: 3
+            })
+        );
+        assert_eq!(
+            Grid::new(usize::MAX, 2, vec![1, 2]),
+            Err(GridError::SizeMismatch {
+                expected: usize::MAX,
+                found: 2
+            })
+        );
+        assert_eq!(Grid::parse("", Some), Grid::new(0, 0, vec![]));
+    }
+
+    #[test]
+    fn test_parse_padded() {
+        let grid = Grid::parse_padded("abc\nd\n\nef\n", '.', Some).unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (3, 4));
+        assert_eq!(
+            grid.rows()
+                .map(|row| row.iter().collect())
+                .collect::<Vec<String>>(),
+            vec!["abc", "d..", "...", "ef."]
+        );
+        assert_eq!(
+            Grid::parse_padded("ab\nc\n\n\n", '.', Some),
+            Grid::parse("ab\nc.", Some)
+        );
+        assert_eq!(
+            Grid::parse("ab\r\ncd\r\n", Some),
+            Grid::parse("ab\ncd", Some)
+        );
+        assert_eq!(
+            Grid::parse_padded("1\n22", 'x', |ch| ch.to_digit(10)),
+            Err(GridError::InvalidCell {
+                point: Point::new(0, 1),
+                ch: 'x'
+            })
+        );
+    }
+}
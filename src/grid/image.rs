@@ -0,0 +1,171 @@
+use std::{fs, io, path::Path};
+
+use super::Grid;
+
+pub type Rgb = [u8; 3];
+
+impl<T> Grid<T> {
+    /// Each cell as a `cell_size` square of `colour(cell)`, as RGB rows from
+    /// the top.
+    fn pixels(&self, cell_size: usize, colour: impl Fn(&T) -> Rgb) -> Vec<Vec<Rgb>> {
+        return self
+            .rows()
+            .flat_map(|cells| {
+                let row: Vec<Rgb> = cells
+                    .iter()
+                    .flat_map(|cell| vec![colour(cell); cell_size])
+                    .collect();
+                vec![row; cell_size]
+            })
+            .collect();
+    }
+
+    /// The image's width and height in pixels, or an error if its RGB pixel
+    /// data would overflow `usize`.
+    fn image_size(&self, cell_size: usize) -> io::Result<(usize, usize)> {
+        let size = || {
+            let width = self.width.checked_mul(cell_size)?;
+            let height = self.height.checked_mul(cell_size)?;
+            width.checked_mul(3)?.checked_mul(height)?;
+            return Some((width, height));
+        };
+
+        return size().ok_or_else(too_large);
+    }
+
+    /// A binary PPM (P6) image, or an error if it would be too large to hold.
+    pub fn to_ppm(&self, cell_size: usize, colour: impl Fn(&T) -> Rgb) -> io::Result<Vec<u8>> {
+        let (width, height) = self.image_size(cell_size)?;
+
+        let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for row in self.pixels(cell_size, colour) {
+            image.extend(row.iter().flatten());
+        }
+
+        return Ok(image);
+    }
+
+    /// An uncompressed 24-bit BMP image, or an error if its sizes don't fit
+    /// the header's 32-bit fields.
+    pub fn to_bmp(&self, cell_size: usize, colour: impl Fn(&T) -> Rgb) -> io::Result<Vec<u8>> {
+        let (width, height) = self.image_size(cell_size)?;
+        // Rows are padded to a multiple of four bytes.
+        let row_size = (width * 3)
+            .checked_next_multiple_of(4)
+            .ok_or_else(too_large)?;
+        let data_size = row_size.checked_mul(height).ok_or_else(too_large)?;
+        let file_size = data_size.checked_add(54).ok_or_else(too_large)?;
+
+        // The header stores sizes in 32 bits.
+        let unsigned = |size: usize| u32::try_from(size).map_err(|_| too_large());
+        let signed = |size: usize| i32::try_from(size).map_err(|_| too_large());
+        let (file_size_field, data_size_field) = (unsigned(file_size)?, unsigned(data_size)?);
+        let (width_field, height_field) = (signed(width)?, signed(height)?);
+
+        let mut image = Vec::with_capacity(file_size);
+        image.extend(b"BM");
+        image.extend(file_size_field.to_le_bytes());
+        image.extend(0u32.to_le_bytes());
+        image.extend(54u32.to_le_bytes());
+        image.extend(40u32.to_le_bytes());
+        image.extend(width_field.to_le_bytes());
+        image.extend(height_field.to_le_bytes());
+        image.extend(1u16.to_le_bytes());
+        image.extend(24u16.to_le_bytes());
+        image.extend(0u32.to_le_bytes());
+        image.extend(data_size_field.to_le_bytes());
+        image.extend(2835i32.to_le_bytes());
+        image.extend(2835i32.to_le_bytes());
+        image.extend(0u32.to_le_bytes());
+        image.extend(0u32.to_le_bytes());
+
+        // BMP stores rows bottom-up and pixels as BGR.
+        for row in self.pixels(cell_size, colour).iter().rev() {
+            for [r, g, b] in row {
+                image.extend([*b, *g, *r]);
+            }
+            image.resize(image.len() + row_size - width * 3, 0);
+        }
+
+        return Ok(image);
+    }
+
+    /// Writes a BMP if `path` ends in `.bmp`, a PPM otherwise.
+    pub fn write_image(
+        &self,
+        path: impl AsRef<Path>,
+        cell_size: usize,
+        colour: impl Fn(&T) -> Rgb,
+    ) -> io::Result<()> {
+        let path = path.as_ref();
+        let is_bmp = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("bmp"));
+
+        let image = if is_bmp {
+            self.to_bmp(cell_size, colour)?
+        } else {
+            self.to_ppm(cell_size, colour)?
+        };
+
+        return fs::write(path, image);
+    }
+}
+
+fn too_large() -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidInput, "image too large");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::grid::Grid;
+
+    use super::Rgb;
+
+    fn colour(cell: &u8) -> Rgb {
+        return [*cell, 0, 255 - *cell];
+    }
+
+    #[test]
+    fn test_to_ppm() {
+        let grid = Grid::new(2, 1, vec![0u8, 255]).unwrap();
+
+        let mut expected = b"P6\n4 2\n255\n".to_vec();
+        for _ in 0..2 {
+            expected.extend([0, 0, 255, 0, 0, 255, 255, 0, 0, 255, 0, 0]);
+        }
+        assert_eq!(grid.to_ppm(2, colour).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_to_bmp() {
+        let grid = Grid::new(1, 2, vec![10u8, 20]).unwrap();
+        let image = grid.to_bmp(1, colour).unwrap();
+
+        // One pixel per row padded to four bytes, after a 54-byte header.
+        assert_eq!(image.len(), 54 + 2 * 4);
+        assert_eq!(&image[0..2], b"BM");
+        assert_eq!(&image[2..6], &62u32.to_le_bytes());
+        assert_eq!(&image[18..22], &1i32.to_le_bytes());
+        assert_eq!(&image[22..26], &2i32.to_le_bytes());
+        assert_eq!(&image[28..30], &24u16.to_le_bytes());
+        // Bottom row (20) first, in BGR order.
+        assert_eq!(&image[54..], &[235, 0, 20, 0, 245, 0, 10, 0]);
+    }
+
+    #[test]
+    fn test_image_too_large() {
+        // About 9.4 GB of pixel data, past the BMP size fields.
+        let grid = Grid::filled(140, 140, 0u8);
+        assert_eq!(
+            grid.to_bmp(400, colour).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        let grid = Grid::filled(2, 1, 0u8);
+        assert!(grid.to_ppm(usize::MAX, colour).is_err());
+        assert!(grid.to_bmp(usize::MAX / 2, colour).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+};
+
+use super::{Grid, Point};
+
+/// A route from start to goal, both included, and what it cost.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route<C> {
+    pub points: Vec<Point>,
+    pub cost: C,
+}
+
+impl Point {
+    #[allow(dead_code)]
+    pub fn manhattan_distance(&self, other: Point) -> usize {
+        return self.row.abs_diff(other.row) + self.col.abs_diff(other.col);
+    }
+}
+
+/// Walks `came_from` back from `goal` to the start.
+#[allow(dead_code)]
+fn reconstruct(came_from: &Grid<Option<Point>>, goal: Point) -> Vec<Point> {
+    let mut points = vec![goal];
+    while let Some(previous) = came_from[*points.last().unwrap()] {
+        points.push(previous);
+    }
+    points.reverse();
+
+    return points;
+}
+
+#[allow(dead_code)]
+impl<T> Grid<T> {
+    /// The fewest-steps route from `start` to `goal` moving up, down, left
+    /// and right, its cost being the number of steps. `passable` is asked
+    /// about each cell stepped onto, never about `start`.
+    pub fn bfs(
+        &self,
+        start: Point,
+        goal: Point,
+        passable: impl Fn(Point, &T) -> bool,
+    ) -> Option<Route<usize>> {
+        if !self.contains(start) {
+            return None;
+        }
+
+        let mut came_from = self.map(|_| None);
+        let mut seen = self.map(|_| false);
+        let mut queue = VecDeque::from([start]);
+        seen[start] = true;
+
+        while let Some(point) = queue.pop_front() {
+            if point == goal {
+                let points = reconstruct(&came_from, goal);
+                return Some(Route {
+                    cost: points.len() - 1,
+                    points,
+                });
+            }
+
+            for next in self.neighbours4(point) {
+                if !seen[next] && passable(next, &self[next]) {
+                    seen[next] = true;
+                    came_from[next] = Some(point);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        return None;
+    }
+
+    /// The cheapest route from `start` to `goal` moving up, down, left and
+    /// right, where stepping onto a cell costs `cost` of it. `start` itself is
+    /// free and never checked with `passable`.
+    pub fn dijkstra(
+        &self,
+        start: Point,
+        goal: Point,
+        passable: impl Fn(Point, &T) -> bool,
+        cost: impl Fn(Point, &T) -> u64,
+    ) -> Option<Route<u64>> {
+        return self.astar(start, goal, passable, cost, |_| 0);
+    }
+
+    /// Like `dijkstra`, guided by `heuristic`, an estimate of the cost left
+    /// from a point to `goal`. The route is only guaranteed cheapest if the
+    /// estimate never exceeds the real cost. Routes costing more than a `u64`
+    /// holds are never taken.
+    pub fn astar(
+        &self,
+        start: Point,
+        goal: Point,
+        passable: impl Fn(Point, &T) -> bool,
+        cost: impl Fn(Point, &T) -> u64,
+        heuristic: impl Fn(Point) -> u64,
+    ) -> Option<Route<u64>> {
+        if !self.contains(start) {
+            return None;
+        }
+
+        let mut came_from = self.map(|_| None);
+        let mut best = self.map(|_| None);
+        let mut queue = BinaryHeap::from([Reverse((heuristic(start), 0, start))]);
+        best[start] = Some(0);
+
+        while let Some(Reverse((_, spent, point))) = queue.pop() {
+            if point == goal {
+                return Some(Route {
+                    points: reconstruct(&came_from, goal),
+                    cost: spent,
+                });
+            }
+            if best[point].is_some_and(|best| spent > best) {
+                continue;
+            }
+
+            for next in self.neighbours4(point) {
+                if !passable(next, &self[next]) {
+                    continue;
+                }
+
+                let Some(total) = spent.checked_add(cost(next, &self[next])) else {
+                    continue;
+                };
+                if best[next].is_none_or(|best| total < best) {
+                    best[next] = Some(total);
+                    came_from[next] = Some(point);
+                    queue.push(Reverse((
+                        total.saturating_add(heuristic(next)),
+                        total,
+                        next,
+                    )));
+                }
+            }
+        }
+
+        return None;
+    }
+
+    /// The number of steps up, down, left and right from the nearest of
+    /// `sources` to every cell, or `None` where no source can reach.
+    /// `passable` is asked about each cell stepped onto, never the sources.
+    pub fn distance_map(
+        &self,
+        sources: impl IntoIterator<Item = Point>,
+        passable: impl Fn(Point, &T) -> bool,
+    ) -> Grid<Option<usize>> {
+        let mut distances = self.map(|_| None);
+        let mut queue = VecDeque::new();
+        for source in sources {
+            if self.contains(source) && distances[source].is_none() {
+                distances[source] = Some(0);
+                queue.push_back(source);
+            }
+        }
+
+        while let Some(point) = queue.pop_front() {
+            let distance = distances[point].unwrap();
+
+            for next in self.neighbours4(point) {
+                if distances[next].is_none() && passable(next, &self[next]) {
+                    distances[next] = Some(distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        return distances;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::grid::{Grid, Point};
+
+    const MAZE: &str = indoc! {"
+        S.#.....
+        .##.###.
+        ....#..G
+    "};
+
+    fn find(grid: &Grid<char>, ch: char) -> Point {
+        return grid.iter().find(|(_, &cell)| cell == ch).unwrap().0;
+    }
+
+    fn open(_: Point, ch: &char) -> bool {
+        return *ch != '#';
+    }
+
+    #[test]
+    fn test_bfs() {
+        let grid = Grid::parse(MAZE, Some).unwrap();
+        let (start, goal) = (find(&grid, 'S'), find(&grid, 'G'));
+
+        let route = grid.bfs(start, goal, open).unwrap();
+        assert_eq!(route.cost, 13);
+        assert_eq!(route.points.len(), 14);
+        assert_eq!(route.points.first(), Some(&start));
+        assert_eq!(route.points.last(), Some(&goal));
+        assert!(route
+            .points
+            .windows(2)
+            .all(|pair| pair[0].manhattan_distance(pair[1]) == 1 && grid[pair[1]] != '#'));
+
+        assert_eq!(
+            grid.bfs(start, start, open).map(|route| route.points),
+            Some(vec![start])
+        );
+        assert_eq!(grid.bfs(start, goal, |_, ch| *ch == '.'), None);
+        assert_eq!(grid.bfs(Point::new(5, 0), goal, open), None);
+    }
+
+    #[test]
+    fn test_dijkstra_and_astar() {
+        // Digits cost what they say; the direct row of 9s loses to the detour.
+        let grid = Grid::parse("1999\n1111\n###1", |ch| {
+            ch.to_digit(10).map(u64::from).or(Some(0))
+        })
+        .unwrap();
+        let walls = Grid::parse("1999\n1111\n###1", Some).unwrap();
+        let passable = |point: Point, _: &u64| walls[point] != '#';
+        let cost = |_: Point, cost: &u64| *cost;
+        let (start, goal) = (Point::new(0, 0), Point::new(2, 3));
+
+        let expected = vec![
+            Point::new(0, 0),
+            Point::new(1, 0),
+            Point::new(1, 1),
+            Point::new(1, 2),
+            Point::new(1, 3),
+            Point::new(2, 3),
+        ];
+        let route = grid.dijkstra(start, goal, passable, cost).unwrap();
+        assert_eq!(route.points, expected);
+        assert_eq!(route.cost, 5);
+
+        let heuristic = |point: Point| point.manhattan_distance(goal) as u64;
+        let route = grid.astar(start, goal, passable, cost, heuristic).unwrap();
+        assert_eq!(route.points, expected);
+        assert_eq!(route.cost, 5);
+
+        assert_eq!(grid.dijkstra(start, Point::new(2, 0), passable, cost), None);
+    }
+
+    #[test]
+    fn test_astar_costs_do_not_overflow() {
+        let grid = Grid::new(3, 1, vec![0, u64::MAX, u64::MAX]).unwrap();
+        let (start, goal) = (Point::new(0, 0), Point::new(0, 2));
+        let cost = |_: Point, cost: &u64| *cost;
+
+        assert_eq!(
+            grid.dijkstra(start, Point::new(0, 1), |_, _| true, cost)
+                .map(|route| route.cost),
+            Some(u64::MAX)
+        );
+        assert_eq!(grid.dijkstra(start, goal, |_, _| true, cost), None);
+        assert_eq!(
+            grid.astar(start, goal, |_, _| true, |_, _| 1, |_| u64::MAX)
+                .map(|route| route.cost),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_astar_matches_bfs_on_maze() {
+        let grid = Grid::parse(MAZE, Some).unwrap();
+        let (start, goal) = (find(&grid, 'S'), find(&grid, 'G'));
+
+        let route = grid
+            .astar(
+                start,
+                goal,
+                open,
+                |_, _| 1,
+                |point| point.manhattan_distance(goal) as u64,
+            )
+            .unwrap();
+        assert_eq!(route.cost, 13);
+        assert_eq!(
+            route.cost as usize,
+            grid.bfs(start, goal, open).unwrap().cost
+        );
+    }
+
+    #[test]
+    fn test_distance_map() {
+        let grid = Grid::parse("a.#.\n..#.\n...b", Some).unwrap();
+        let sources = [find(&grid, 'a'), find(&grid, 'b')];
+
+        let distances = grid.distance_map(sources, open);
+        let rendered: Vec<String> = distances
+            .rows()
+            .map(|row| {
+                row.iter()
+                    .map(|distance| match distance {
+                        Some(distance) => char::from_digit(*distance as u32, 10).unwrap(),
+                        None => '#',
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(rendered, vec!["01#2", "12#1", "2210"]);
+
+        let isolated = Grid::parse("a#.", Some).unwrap();
+        assert_eq!(
+            isolated.distance_map([Point::new(0, 0)], open),
+            Grid::new(3, 1, vec![Some(0), None, None]).unwrap()
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use std::ops::Index;
+
+use super::{Grid, Point};
+
+#[allow(dead_code)]
+impl<T: Clone> Grid<T> {
+    pub fn transpose(&self) -> Grid<T> {
+        return Grid::from_fn(self.height, self.width, |point| {
+            self[Point::new(point.col, point.row)].clone()
+        });
+    }
+
+    /// Rotates a quarter turn clockwise, so the first column becomes the
+    /// first row read bottom to top.
+    pub fn rotate_cw(&self) -> Grid<T> {
+        return Grid::from_fn(self.height, self.width, |point| {
+            self[Point::new(self.height - 1 - point.col, point.row)].clone()
+        });
+    }
+
+    pub fn rotate_ccw(&self) -> Grid<T> {
+        return Grid::from_fn(self.height, self.width, |point| {
+            self[Point::new(point.col, self.width - 1 - point.row)].clone()
+        });
+    }
+
+    /// Mirrors left to right.
+    pub fn flip_h(&self) -> Grid<T> {
+        return Grid::from_fn(self.width, self.height, |point| {
+            self[Point::new(point.row, self.width - 1 - point.col)].clone()
+        });
+    }
+
+    /// Mirrors top to bottom.
+    pub fn flip_v(&self) -> Grid<T> {
+        return Grid::from_fn(self.width, self.height, |point| {
+            self[Point::new(self.height - 1 - point.row, point.col)].clone()
+        });
+    }
+}
+
+/// A borrowed window of a grid, with (0, 0) at the window's top-left corner.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct SubGrid<'a, T> {
+    grid: &'a Grid<T>,
+    origin: Point,
+    width: usize,
+    height: usize,
+}
+
+// Not derived, as that would require `T: Copy` when a view only copies its
+// reference to the grid.
+impl<T> Clone for SubGrid<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SubGrid<'_, T> {}
+
+/// Whether `length` cells from `start` stay within `limit`, without
+/// overflowing.
+fn fits(start: usize, length: usize, limit: usize) -> bool {
+    return start.checked_add(length).is_some_and(|end| end <= limit);
+}
+
+impl<T> Grid<T> {
+    /// The `height` by `width` window starting at `origin`, or `None` if it
+    /// doesn't fit inside the grid.
+    #[allow(dead_code)]
+    pub fn view(&self, origin: Point, height: usize, width: usize) -> Option<SubGrid<'_, T>> {
+        if !fits(origin.row, height, self.height) || !fits(origin.col, width, self.width) {
+            return None;
+        }
+
+        return Some(SubGrid {
+            grid: self,
+            origin,
+            width,
+            height,
+        });
+    }
+}
+
+#[allow(dead_code)]
+impl<'a, T> SubGrid<'a, T> {
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        return point.row < self.height && point.col < self.width;
+    }
+
+    /// Where `point` of this view lies in the underlying grid.
+    pub fn to_parent(self, point: Point) -> Point {
+        return Point::new(self.origin.row + point.row, self.origin.col + point.col);
+    }
+
+    pub fn get(&self, point: Point) -> Option<&'a T> {
+        if !self.contains(point) {
+            return None;
+        }
+
+        return self.grid.get(self.to_parent(point));
+    }
+
+    pub fn row(&self, row: usize) -> Option<&'a [T]> {
+        if row >= self.height {
+            return None;
+        }
+
+        let cells = self.grid.row(self.origin.row + row)?;
+        return Some(&cells[self.origin.col..self.origin.col + self.width]);
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> + '_ {
+        return (0..self.height).filter_map(|row| self.row(row));
+    }
+
+    /// Every cell with its point in this view's coordinates, in row-major
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &'a T)> + '_ {
+        return (0..self.height).flat_map(move |row| {
+            (0..self.width).map(move |col| {
+                let point = Point::new(row, col);
+                (point, &self.grid[self.to_parent(point)])
+            })
+        });
+    }
+
+    /// A window of this view, in this view's coordinates.
+    pub fn view(&self, origin: Point, height: usize, width: usize) -> Option<SubGrid<'a, T>> {
+        if !fits(origin.row, height, self.height) || !fits(origin.col, width, self.width) {
+            return None;
+        }
+
+        return Some(SubGrid {
+            grid: self.grid,
+            origin: self.to_parent(origin),
+            width,
+            height,
+        });
+    }
+
+    pub fn to_grid(self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        return Grid::from_fn(self.width, self.height, |point| {
+            self.grid[self.to_parent(point)].clone()
+        });
+    }
+}
+
+impl<T> Index<Point> for SubGrid<'_, T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &T {
+        assert!(self.contains(point), "{} is outside the view", point);
+
+        return &self.grid[self.to_parent(point)];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::{Grid, Point};
+
+    fn text(grid: &Grid<char>) -> String {
+        return grid
+            .rows()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    #[test]
+    fn test_transforms() {
+        let grid = Grid::parse("abc\ndef", Some).unwrap();
+
+        assert_eq!(text(&grid.transpose()), "ad\nbe\ncf");
+        assert_eq!(text(&grid.rotate_cw()), "da\neb\nfc");
+        assert_eq!(text(&grid.rotate_ccw()), "cf\nbe\nad");
+        assert_eq!(text(&grid.flip_h()), "cba\nfed");
+        assert_eq!(text(&grid.flip_v()), "def\nabc");
+
+        assert_eq!(grid.rotate_cw().rotate_ccw(), grid);
+        assert_eq!(grid.rotate_cw().rotate_cw(), grid.flip_h().flip_v());
+        assert_eq!(grid.transpose().transpose(), grid);
+        assert_eq!(grid.transpose(), grid.rotate_cw().flip_h());
+    }
+
+    #[test]
+    fn test_transforms_ragged_and_empty() {
+        let grid = Grid::parse_padded("ab\nc\ndef", '.', Some).unwrap();
+
+        assert_eq!(text(&grid.rotate_cw()), "dca\ne.b\nf..");
+        assert_eq!(text(&grid.flip_h()), ".ba\n..c\nfed");
+
+        let empty: Grid<char> = Grid::parse("", Some).unwrap();
+        assert_eq!(empty.rotate_cw(), empty);
+    }
+
+    #[test]
+    fn test_sub_grid() {
+        let grid = Grid::parse("abcd\nefgh\nijkl", Some).unwrap();
+        let view = grid.view(Point::new(1, 1), 2, 3).unwrap();
+
+        assert_eq!((view.width(), view.height()), (3, 2));
+        assert_eq!(view[Point::new(0, 0)], 'f');
+        assert_eq!(view.get(Point::new(1, 2)), Some(&'l'));
+        assert_eq!(view.get(Point::new(2, 0)), None);
+        assert_eq!(view.get(Point::new(0, 3)), None);
+        assert_eq!(view.row(1), Some(&['j', 'k', 'l'][..]));
+        assert_eq!(
+            view.rows()
+                .map(|row| row.iter().collect())
+                .collect::<Vec<String>>(),
+            vec!["fgh", "jkl"]
+        );
+        assert_eq!(view.iter().nth(4), Some((Point::new(1, 1), &'k')));
+        assert_eq!(view.to_parent(Point::new(1, 2)), Point::new(2, 3));
+        assert_eq!(text(&view.to_grid()), "fgh\njkl");
+
+        let inner = view.view(Point::new(0, 1), 2, 2).unwrap();
+        assert_eq!(text(&inner.to_grid()), "gh\nkl");
+        assert!(view.view(Point::new(1, 1), 2, 1).is_none());
+
+        assert!(grid.view(Point::new(0, 2), 1, 3).is_none());
+        assert!(grid.view(Point::new(3, 0), 0, 4).is_some());
+        assert!(grid.view(Point::new(1, 0), usize::MAX, 1).is_none());
+        assert!(view.view(Point::new(0, 1), 1, usize::MAX).is_none());
+    }
+
+    #[test]
+    fn test_sub_grid_is_copy_for_any_cell() {
+        #[derive(Debug, PartialEq)]
+        struct Cell(u8);
+
+        let grid = Grid::new(2, 1, vec![Cell(1), Cell(2)]).unwrap();
+        let view = grid.view(Point::new(0, 1), 1, 1).unwrap();
+        let copy = view;
+
+        assert_eq!(view.get(Point::new(0, 0)), copy.get(Point::new(0, 0)));
+    }
+}
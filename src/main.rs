@@ -1,9 +1,19 @@
+#![allow(clippy::needless_return)]
+
 mod day1;
 mod day2;
 mod day3;
 mod day4;
 mod day5;
+mod grid;
 
 fn main() {
-    day5::run();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(|arg| arg.as_str()) {
+        Some("day1") => day1::run_command(&args[1..]),
+        Some("day2") => day2::run_command(&args[1..]),
+        Some("day3") => day3::run_command(&args[1..]),
+        _ => day5::run(),
+    }
 }